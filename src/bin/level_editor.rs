@@ -1,120 +1,607 @@
-use bevy::{prelude::*, winit::WinitSettings, sprite::MaterialMesh2dBundle};
-use bevy_prototype_lyon::prelude::ShapePlugin;
-use rocks_plugin::RockBundle;
-use std::fs::File;
-use std::io::prelude::*;
-use serde_json;
-use serde::{Serialize, Deserialize};
-
-#[path="../rocks_plugin.rs"]
-mod rocks_plugin;
-use rocks_plugin::*;
-
-#[allow(dead_code)]
+use bevy::{prelude::*, winit::WinitSettings, input::mouse::{MouseWheel, MouseScrollUnit}};
+use bevy_prototype_lyon::prelude::*;
+use bevy_ier_curves::{
+    rocks_plugin::*,
+    level_format::*,
+    geometry::point_in_polygon,
+    camera_tools::*,
+    game::{GamePlugin, AppState, Level, BACKGROUND_COLOR}
+};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+
+// Something that happened to the level, with enough information to undo it
 enum Action {
-    AddBorderPoint,
-    AddAnchor,
+    AddBorderPoint(usize),
+    AddAnchor(usize),
     MoveEndFrom(Vec2),
     MovePlayAreaBy(Vec2),
     MoveBorderPointFrom(usize, Vec2),
     MoveAnchorFrom(usize, Vec2),
     DeletedBorderPoint(usize, Vec2),
-    DeletedAnchorPoint(usize, Vec2)
+    DeletedAnchorPoint(usize, Vec2),
+    // the id of the rock and the index of the point
+    AddRockPoint(u64, usize),
+    MoveRockPointFrom(u64, usize, Vec2),
+    DeletedRockPoint(u64, usize, Vec2),
+    AddRock(u64),
+    DeletedRock(usize, RockInfo),
+    ChangedRockStyleFrom(u64, RockStyle)
+}
+
+impl Action {
+    // Undoes the action and returns the action that would undo that, which is how redo works.
+    // Returns None if the level no longer has what the action refers to
+    fn revert(self, file_info: &mut LevelFile) -> Option<Action> {
+        Some(match self {
+            Action::AddBorderPoint(i) => Action::DeletedBorderPoint(i, remove_point(&mut file_info.border_points, i)?),
+            Action::AddAnchor(i) => Action::DeletedAnchorPoint(i, remove_point(&mut file_info.anchors, i)?),
+            Action::DeletedBorderPoint(i, p) => {
+                insert_point(&mut file_info.border_points, i, p)?;
+                Action::AddBorderPoint(i)
+            },
+            Action::DeletedAnchorPoint(i, p) => {
+                insert_point(&mut file_info.anchors, i, p)?;
+                Action::AddAnchor(i)
+            },
+            Action::MoveBorderPointFrom(i, from) => move_point_back(file_info, PointRef::BorderPoint(i), from)?,
+            Action::MoveAnchorFrom(i, from) => move_point_back(file_info, PointRef::Anchor(i), from)?,
+            Action::MoveEndFrom(from) => move_point_back(file_info, PointRef::End, from)?,
+            Action::MoveRockPointFrom(id, i, from) => move_point_back(file_info, PointRef::RockPoint(id, i), from)?,
+            Action::MovePlayAreaBy(offset) => {
+                move_play_area_by(file_info, -offset);
+                Action::MovePlayAreaBy(-offset)
+            },
+            Action::AddRockPoint(id, i) => {
+                let index = file_info.rocks.iter().position(|rock| rock.id == id)?;
+
+                // taking away a rock's only point takes away the rock
+                if file_info.rocks[index].points.len() == 1 && i == 0 {
+                    Action::DeletedRock(index, file_info.rocks.remove(index))
+                } else {
+                    Action::DeletedRockPoint(id, i, remove_point(&mut file_info.rocks[index].points, i)?)
+                }
+            },
+            Action::DeletedRockPoint(id, i, p) => {
+                insert_point(&mut file_info.rock_mut(id)?.points, i, p)?;
+                Action::AddRockPoint(id, i)
+            },
+            Action::AddRock(id) => {
+                let index = file_info.rocks.iter().position(|rock| rock.id == id)?;
+                Action::DeletedRock(index, file_info.rocks.remove(index))
+            },
+            Action::DeletedRock(index, rock) => {
+                let id = rock.id;
+                let index = index.min(file_info.rocks.len());
+
+                file_info.rocks.insert(index, rock);
+                Action::AddRock(id)
+            },
+            Action::ChangedRockStyleFrom(id, style) => {
+                let rock = file_info.rock_mut(id)?;
+                Action::ChangedRockStyleFrom(id, std::mem::replace(&mut rock.style, style))
+            }
+        })
+    }
+
+    // Folds a move made later in the same drag into this one
+    fn merge(&self, later: &Action) -> Option<Action> {
+        Some(match (self, later) {
+            (Action::MoveBorderPointFrom(i, from), Action::MoveBorderPointFrom(j, _)) if i == j => {
+                Action::MoveBorderPointFrom(*i, *from)
+            },
+            (Action::MoveAnchorFrom(i, from), Action::MoveAnchorFrom(j, _)) if i == j => {
+                Action::MoveAnchorFrom(*i, *from)
+            },
+            (Action::MoveEndFrom(from), Action::MoveEndFrom(_)) => Action::MoveEndFrom(*from),
+            (Action::MoveRockPointFrom(id, i, from), Action::MoveRockPointFrom(other_id, j, _)) if id == other_id && i == j => {
+                Action::MoveRockPointFrom(*id, *i, *from)
+            },
+            (Action::MovePlayAreaBy(offset), Action::MovePlayAreaBy(later_offset)) => {
+                Action::MovePlayAreaBy(*offset + *later_offset)
+            },
+            _ => return None
+        })
+    }
+}
+
+fn remove_point(points: &mut Vec<[f32; 2]>, i: usize) -> Option<Vec2> {
+    if i >= points.len() { return None; }
+
+    let [x, y] = points.remove(i);
+    Some(Vec2::new(x, y))
+}
+
+fn insert_point(points: &mut Vec<[f32; 2]>, i: usize, p: Vec2) -> Option<()> {
+    if i > points.len() { return None; }
+
+    points.insert(i, [p.x, p.y]);
+    Some(())
+}
+
+fn move_point_back(file_info: &mut LevelFile, point_ref: PointRef, from: Vec2) -> Option<Action> {
+    let to = get_point(file_info, point_ref)?;
+    set_point(file_info, point_ref, from);
+
+    Some(move_action(point_ref, to))
+}
+
+// The action for a point that was moved away from `from`
+fn move_action(point_ref: PointRef, from: Vec2) -> Action {
+    match point_ref {
+        PointRef::BorderPoint(i) => Action::MoveBorderPointFrom(i, from),
+        PointRef::Anchor(i) => Action::MoveAnchorFrom(i, from),
+        PointRef::End => Action::MoveEndFrom(from),
+        PointRef::RockPoint(id, i) => Action::MoveRockPointFrom(id, i, from)
+    }
+}
+
+// How many actions can be undone
+const HISTORY_LIMIT: usize = 500;
+
+#[derive(Resource)]
+struct History {
+    undo_stack: VecDeque<Action>,
+    redo_stack: Vec<Action>,
+    // moves get merged into the last action until the drag making them ends
+    coalescing: bool
+}
+
+impl History {
+    fn new() -> Self {
+        History { undo_stack: VecDeque::new(), redo_stack: Vec::new(), coalescing: false }
+    }
+
+    fn push(&mut self, action: Action) {
+        // a new action means whatever was undone can't be redone anymore
+        self.redo_stack.clear();
+        self.coalescing = false;
+        self.undo_stack.push_back(action);
+
+        if self.undo_stack.len() > HISTORY_LIMIT {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    // Records a move that's part of a drag, so the whole drag gets undone at once
+    fn push_move(&mut self, action: Action) {
+        if self.coalescing {
+            if let Some(merged) = self.undo_stack.back().and_then(|last| last.merge(&action)) {
+                *self.undo_stack.back_mut().unwrap() = merged;
+                return;
+            }
+        }
+
+        self.push(action);
+        self.coalescing = true;
+    }
+
+    fn end_move(&mut self) {
+        self.coalescing = false;
+    }
+
+    fn undo(&mut self, file_info: &mut LevelFile) {
+        self.coalescing = false;
+
+        if let Some(redo_action) = self.undo_stack.pop_back().and_then(|action| action.revert(file_info)) {
+            self.redo_stack.push(redo_action);
+        }
+    }
+
+    fn redo(&mut self, file_info: &mut LevelFile) {
+        self.coalescing = false;
+
+        if let Some(undo_action) = self.redo_stack.pop().and_then(|action| action.revert(file_info)) {
+            self.undo_stack.push_back(undo_action);
+        }
+    }
 }
 
-#[allow(dead_code)]
 #[derive(Resource)]
 enum Mode {
     Move,
     AddBorderPoint,
     AddAnchor,
+    AddRock,
     Deletion
 }
 
+impl Mode {
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Move => "Move points",
+            Mode::AddBorderPoint => "Add border points",
+            Mode::AddAnchor => "Add anchor points",
+            Mode::AddRock => "Add rocks",
+            Mode::Deletion => "Delete points"
+        }
+    }
+}
+
+// The rock being edited, it stays open while its points are still being placed
+#[derive(Resource)]
+struct SelectedRock {
+    id: Option<u64>,
+    open: bool
+}
+
+// A point in the level that can be moved or deleted
+#[derive(Clone, Copy, PartialEq)]
+enum PointRef {
+    BorderPoint(usize),
+    Anchor(usize),
+    End,
+    // the id of the rock and the index of the point
+    RockPoint(u64, usize)
+}
+
+// Dragging empty space in move mode moves the whole play area. The game always starts
+// the player at the origin, so dragging the start moves the play area the other way
+#[derive(Clone, Copy, PartialEq)]
+enum DragTarget {
+    Point(PointRef),
+    PlayArea,
+    Start
+}
+
+struct Drag {
+    target: DragTarget,
+    last_cursor_pos: Vec2
+}
+
 #[derive(Resource)]
-struct Actions(Vec<Action>);
+struct Dragging(Option<Drag>);
+
+// How close the cursor has to be to a point to grab it, in pixels
+const POINT_HIT_RADIUS: f32 = 10.0;
+
+// Everything drawn from the level file, which gets redrawn whenever it changes
+#[derive(Component)]
+struct LevelShape;
+
+#[derive(Component)]
+struct ModeText;
+
+const SELECTED_ROCK_COLOR: Color = Color::ORANGE;
+const SELECTED_ROCK_OUTLINE_WIDTH: f32 = 4.0;
+
+// Same sizes as in the game
+const ANCHOR_COLOR: Color = Color::rgb(0.3, 0.3, 0.6);
+const ANCHOR_RADIUS: f32 = 5.0;
+const END_COLOR: Color = Color::rgb(0.2, 0.6, 0.2);
+const END_RADIUS: f32 = 15.0;
+const START_COLOR: Color = Color::RED;
+const START_SIZE: f32 = 12.0;
+// The line from the start through each anchor to the end, the order the course goes in
+const COURSE_COLOR: Color = Color::rgba(0.3, 0.3, 0.6, 0.4);
+const COURSE_WIDTH: f32 = 2.0;
 
 #[derive(Resource)]
 struct CursorPos(Vec2);
 
-#[derive(Serialize, Deserialize, Resource)]
-struct FileInfo {
-    border_points: Vec<[f32; 2]>,
-    anchors: Vec<[f32; 2]>,
-    end: [f32; 2]
+// Where new and dragged points go, which is the cursor unless snapping moved it
+#[derive(Resource)]
+struct SnappedCursorPos {
+    pos: Vec2,
+    // the point the new edge starts at, when it was angle snapped
+    guide_from: Option<Vec2>
 }
 
+// How many world units a pixel is, copied from the camera's projection
+#[derive(Resource)]
+struct Zoom(f32);
+
 #[derive(Resource)]
-struct FileRef(File);
+struct Snapping {
+    enabled: bool,
+    grid_size: f32
+}
+
+const DEFAULT_GRID_SIZE: f32 = 25.0;
+const MIN_GRID_SIZE: f32 = 5.0;
+const MAX_GRID_SIZE: f32 = 400.0;
+// Edges snap to multiples of this angle, in degrees
+const SNAP_ANGLE: f32 = 15.0;
+// Grid lines closer together than this, in pixels, get thinned out
+const MIN_GRID_LINE_SPACING: f32 = 8.0;
+
+const GRID_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const SNAP_GUIDE_COLOR: Color = Color::rgb(0.2, 0.5, 0.9);
+const SNAP_MARKER_RADIUS: f32 = 4.0;
+
+#[derive(Component)]
+struct GridLines;
+
+// Where the camera was before play-testing, so editing picks up right where it left off
+#[derive(Resource)]
+struct PlayTestCamera(Option<(Vec3, f32)>);
+
+#[derive(Component)]
+struct SnapGuide;
+
+// Where the level gets saved and what it looked like the last time it was,
+// the level has unsaved changes whenever it doesn't match
+#[derive(Resource)]
+struct SaveState {
+    path: PathBuf,
+    saved: LevelFile
+}
+
+impl SaveState {
+    fn has_unsaved_changes(&self, file_info: &LevelFile) -> bool {
+        *file_info != self.saved
+    }
+}
+
+// Unsaved changes get written to a recovery file this often, in seconds
+const AUTOSAVE_INTERVAL: f32 = 30.0;
+
+#[derive(Resource)]
+struct AutosaveTimer(Timer);
+
+const WINDOW_TITLE: &str = "Bevy-ier curves [Level Editor]";
+
+// How much one line of scrolling zooms in or out
+const ZOOM_PER_LINE: f32 = 1.1;
+// scrolling by pixels (trackpads) zooms this many times slower than by lines
+const PIXELS_PER_LINE: f32 = 20.0;
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 50.0;
+// How much empty space there is around the level after fitting it to the window
+const FIT_MARGIN: f32 = 1.2;
+
+// cmd on mac, ctrl everywhere else
+const COMMAND_KEYS: [KeyCode; 4] = [KeyCode::LWin, KeyCode::RWin, KeyCode::LControl, KeyCode::RControl];
+
+const USAGE: &str = "\
+Usage: level_editor [--levels-dir <dir>] <command>
+
+Commands:
+    create <name>         Create an empty level and open it in the editor
+    edit <name>           Open a level in the editor
+    delete <name>         Delete a level
+    list                  List every level
+    validate [<name>...]  Check that levels can be played, all of them if none are named
+    convert [<name>...]   Upgrade levels to the newest file format, all of them if none are named
+
+Options:
+    --levels-dir <dir>    Where the levels are kept [default: src/bin/levels]";
+
+enum Command {
+    Create(String),
+    Edit(String),
+    Delete(String),
+    List,
+    Validate(Vec<String>),
+    Convert(Vec<String>)
+}
+
+struct Args {
+    levels_dir: PathBuf,
+    command: Command
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut levels_dir = PathBuf::from(LEVELS_DIR);
+        let mut positional = Vec::new();
+
+        while let Some(arg) = args.next() {
+            if arg == "--levels-dir" {
+                levels_dir = args.next().ok_or("--levels-dir needs a directory")?.into();
+            } else if let Some(dir) = arg.strip_prefix("--levels-dir=") {
+                levels_dir = dir.into();
+            } else if arg.starts_with('-') {
+                return Err(format!("Unknown option {}", arg));
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let command = positional.next().ok_or("No command given")?;
+        let mut name = || positional.next().ok_or_else(|| format!("{} needs the name of a level", command));
+
+        let command = match &command[..] {
+            "create" => Command::Create(name()?),
+            "edit" => Command::Edit(name()?),
+            "delete" => Command::Delete(name()?),
+            "list" => Command::List,
+            "validate" => Command::Validate(positional.collect()),
+            "convert" => Command::Convert(positional.collect()),
+            _ => return Err(format!("Unknown command {}", command))
+        };
+
+        Ok(Args { levels_dir, command })
+    }
+
+    fn level_path(&self, name: &str) -> PathBuf {
+        self.levels_dir.join(format!("{}.json", name))
+    }
+
+    // The names of every level in the levels directory, sorted
+    fn level_names(&self) -> Result<Vec<String>, LevelError> {
+        let mut names = Vec::new();
+
+        for entry in fs::read_dir(&self.levels_dir)? {
+            let path = entry?.path();
+
+            if path.extension().and_then(|extension| extension.to_str()) == Some("json") {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+
+        names.sort();
+
+        Ok(names)
+    }
+}
 
 fn main() {
-    let choice = get_choice(
-        "What would you like to do?", 
-            vec![
-                "Create a level",
-                "Modify a level",
-                "Delete a level"
-            ]
-    );
-
-    let file_name = get_file_name(choice);
-
-    if &file_name[..] == "" { return; }
-
-    let mut file_info = FileInfo {
-        border_points: Vec::new(),
-        anchors: Vec::new(),
-        end: [0.0, 200.0]
+    if std::env::args().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
     };
 
-    let file_path = format!("src/bin/levels/{}.json", file_name);
+    match run(args) {
+        Ok(true) => {},
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Returns whether every level the command looked at was fine
+fn run(args: Args) -> Result<bool, LevelError> {
+    match &args.command {
+        Command::Create(name) => {
+            let path = args.level_path(name);
+            let file_info = LevelFile::default();
 
-    let file = match choice {
-        0 => {
-            let mut file = File::create(file_path).unwrap();
-            let _ = file.write(serde_json::to_string_pretty(&file_info).unwrap().as_bytes());
+            // claims the name so an existing level never gets overwritten
+            OpenOptions::new().write(true).create_new(true).open(&path)?;
+            file_info.save(&path)?;
 
-            file
+            open_editor(file_info.clone(), SaveState { path, saved: file_info });
         },
-        1 => {
-            let mut file = File::open(file_path).unwrap();
-            let mut contents = String::new();
-            let _ = file.read_to_string(&mut contents);
+        Command::Edit(name) => {
+            let path = args.level_path(name);
+            let saved = LevelFile::load(&path)?;
+            let recovery_path = recovery_path(&path);
 
-            file_info = serde_json::from_str(&contents).unwrap();
+            // changes that never got saved last time are picked back up, they still
+            // count as unsaved until they are
+            let file_info = match LevelFile::load(&recovery_path) {
+                Ok(recovered) => {
+                    eprintln!("Recovered unsaved changes from {}", recovery_path.display());
+                    recovered
+                },
+                _ => saved.clone()
+            };
 
-            file
+            open_editor(file_info, SaveState { path, saved });
         },
-        2 => {
-            let _ = std::fs::remove_file(file_path);
-            return;
+        Command::Delete(name) => {
+            let path = args.level_path(name);
+
+            fs::remove_file(&path)?;
+            let _ = fs::remove_file(recovery_path(&path));
         },
-        _ => { return; }
-    };
+        Command::List => {
+            for name in args.level_names()? {
+                println!("{}", name);
+            }
+        },
+        Command::Validate(names) => {
+            let names = if names.is_empty() { args.level_names()? } else { names.clone() };
+            let mut all_valid = true;
+
+            for name in names {
+                match LevelFile::load(args.level_path(&name)).and_then(|file_info| file_info.validate()) {
+                    Ok(()) => println!("{}: ok", name),
+                    Err(err) => {
+                        println!("{}: {}", name, err);
+                        all_valid = false;
+                    }
+                }
+            }
+
+            return Ok(all_valid);
+        },
+        Command::Convert(names) => {
+            let names = if names.is_empty() { args.level_names()? } else { names.clone() };
+            let mut all_converted = true;
+
+            for name in names {
+                let path = args.level_path(&name);
+
+                // loading a level upgrades it, so saving it again is all converting takes
+                match LevelFile::load(&path).and_then(|file_info| file_info.save(&path)) {
+                    Ok(()) => println!("{}: version {}", name, CURRENT_VERSION),
+                    Err(err) => {
+                        println!("{}: {}", name, err);
+                        all_converted = false;
+                    }
+                }
+            }
+
+            return Ok(all_converted);
+        }
+    }
 
+    Ok(true)
+}
+
+fn open_editor(file_info: LevelFile, save_state: SaveState) {
     App::new()
     .insert_resource(ClearColor(Color::WHITE))
         .insert_resource(file_info)
-        .insert_resource(FileRef(file))
+        .insert_resource(save_state)
+        .insert_resource(AutosaveTimer(Timer::from_seconds(AUTOSAVE_INTERVAL, TimerMode::Repeating)))
         .insert_resource(WinitSettings::desktop_app())
         .insert_resource(CursorPos(Vec2::ZERO))
-        .insert_resource(Actions(Vec::new()))
+        .insert_resource(SnappedCursorPos { pos: Vec2::ZERO, guide_from: None })
+        .insert_resource(Zoom(1.0))
+        .insert_resource(Snapping { enabled: true, grid_size: DEFAULT_GRID_SIZE })
+        .insert_resource(History::new())
         .insert_resource(Mode::AddBorderPoint)
+        .insert_resource(SelectedRock { id: None, open: false })
+        .insert_resource(Dragging(None))
+        .insert_resource(PlayTestCamera(None))
 
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
-                title: "Bevy-ier curves [Level Editor]".to_string(),
+                title: WINDOW_TITLE.to_string(),
                 ..Default::default()
             },
             ..Default::default()
         }))
         .add_plugin(ShapePlugin)
-        // .add_plugin(RocksPlugin)
+        .add_plugin(GamePlugin { initial_state: AppState::Editing, seed: None })
         .add_startup_system(setup)
-        .add_system(add_point_on_click)
-        .add_system(set_cursor_pos)
+        .add_system_set(
+            SystemSet::on_update(AppState::Editing)
+            .with_system(switch_mode)
+            .with_system(add_point_on_click.after(snap_cursor))
+            .with_system(drag_points.after(snap_cursor))
+            .with_system(delete_point_on_click.after(set_cursor_pos))
+            .with_system(edit_selected_rock)
+            .with_system(redraw_level)
+            .with_system(pan_camera)
+            .with_system(zoom_camera)
+            .with_system(fit_camera_to_level)
+            .with_system(set_cursor_pos.after(pan_camera).after(zoom_camera).after(fit_camera_to_level))
+            .with_system(change_snapping)
+            .with_system(snap_cursor.after(set_cursor_pos).after(change_snapping))
+            .with_system(draw_grid.after(set_cursor_pos))
+            .with_system(draw_snap_guide.after(snap_cursor))
+            .with_system(undo_redo)
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::Editing)
+            .with_system(start_play_test)
+        )
+        .add_system_set(
+            SystemSet::on_enter(AppState::Editing)
+            .with_system(return_to_editing)
+        )
+        .add_system(toggle_play_test)
         .add_system(save_to_file)
-        .add_system(undo)
+        .add_system(autosave)
+        .add_system(show_unsaved_changes)
         .add_system(bevy::window::close_on_esc)
         .run()
     ;
@@ -122,34 +609,29 @@ fn main() {
 
 fn setup(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
-    file_info: Res<FileInfo>
+    asset_server: Res<AssetServer>
 ) {
     commands.spawn(Camera2dBundle::default());
 
-    commands.spawn(MaterialMesh2dBundle {
-        mesh: meshes.add(shape::Circle::default().into()).into(),
-        material: materials.add(ColorMaterial::from(Color::GREEN)),
-        transform: Transform
-            ::from_xyz(0.0, 0.0, 3.0)
-            .with_scale(Vec3::new(10.0, 10.0, 10.0)),
-        ..default()
-    });
-
     let text_style = TextStyle {
         font: asset_server.load("fonts/HankenGrotesk-VariableFont_wght.ttf"), 
         font_size: 30.0, 
         color: Color::BLACK
     };
     
-    commands.spawn(TextBundle::from_sections([
+    commands.spawn((TextBundle::from_sections([
         TextSection {
             value: "Keyboard shortcuts:\n    \
                 m: Move points\n    \
                 b: Add border points\n    \
-                a: Add anchor points\n\n\
+                a: Add anchor points\n    \
+                r: Add rocks (enter: close, backspace: delete, c: change style)\n    \
+                d: Delete points\n    \
+                right drag: Pan, scroll: Zoom, f: Fit the level to the window\n    \
+                g: Toggle snapping (hold alt to flip it), [ and ]: Change the grid size\n    \
+                cmd + z: Undo (shift: redo)\n    \
+                cmd + s: Save (shift: save as a copy)\n    \
+                p: Play-test the level (p again to go back to editing)\n\n\
                 Current mode: ".to_string(),
             style: text_style.clone()
         }, TextSection {
@@ -166,151 +648,988 @@ fn setup(
                 ..default()
             },
             ..default()
-        })
-    );
+        }), ModeText
+    ));
+}
 
-    if file_info.border_points.len() > 1 {
-        commands.spawn(RockBundle::new(
-            file_info.border_points.iter().map(|[x, y]| Vec2::new(*x, *y)).collect()
-        ));
+fn get_point(file_info: &LevelFile, point_ref: PointRef) -> Option<Vec2> {
+    let [x, y] = match point_ref {
+        PointRef::BorderPoint(i) => *file_info.border_points.get(i)?,
+        PointRef::Anchor(i) => *file_info.anchors.get(i)?,
+        PointRef::End => file_info.end,
+        PointRef::RockPoint(id, i) => *file_info.rock(id)?.points.get(i)?
+    };
+
+    Some(Vec2::new(x, y))
+}
+
+fn set_point(file_info: &mut LevelFile, point_ref: PointRef, p: Vec2) {
+    let point = match point_ref {
+        PointRef::BorderPoint(i) => file_info.border_points.get_mut(i),
+        PointRef::Anchor(i) => file_info.anchors.get_mut(i),
+        PointRef::End => Some(&mut file_info.end),
+        PointRef::RockPoint(id, i) => file_info.rock_mut(id).and_then(|rock| rock.points.get_mut(i))
+    };
+
+    if let Some(point) = point {
+        *point = [p.x, p.y];
     }
 }
 
+// Every point in the level along with where it is
+fn all_points(file_info: &LevelFile) -> Vec<(PointRef, Vec2)> {
+    let to_vec2 = |[x, y]: [f32; 2]| Vec2::new(x, y);
+    let mut points = vec![(PointRef::End, to_vec2(file_info.end))];
+
+    points.extend(file_info.border_points.iter().enumerate().map(|(i, p)| (PointRef::BorderPoint(i), to_vec2(*p))));
+    points.extend(file_info.anchors.iter().enumerate().map(|(i, p)| (PointRef::Anchor(i), to_vec2(*p))));
+
+    for rock in file_info.rocks.iter() {
+        points.extend(rock.points.iter().enumerate().map(|(i, p)| (PointRef::RockPoint(rock.id, i), to_vec2(*p))));
+    }
+
+    points
+}
+
+// The closest point to the cursor that passes the filter, as long as it's within the radius
+fn find_point_near(file_info: &LevelFile, cursor_pos: Vec2, radius: f32, filter: impl Fn(PointRef) -> bool) -> Option<PointRef> {
+    all_points(file_info)
+        .into_iter()
+        .filter(|(point_ref, _)| filter(*point_ref))
+        .map(|(point_ref, p)| (point_ref, p.distance(cursor_pos)))
+        .filter(|(_, distance)| *distance < radius)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(point_ref, _)| point_ref)
+}
+
+fn move_play_area_by(file_info: &mut LevelFile, offset: Vec2) {
+    let points = all_points(file_info);
+
+    for (point_ref, p) in points {
+        set_point(file_info, point_ref, p + offset);
+    }
+}
+
+fn switch_mode(
+    keys: Res<Input<KeyCode>>,
+    mut mode: ResMut<Mode>,
+    mut selected_rock: ResMut<SelectedRock>,
+    mut mode_text_query: Query<&mut Text, With<ModeText>>
+) {
+    // keys held with cmd or ctrl are shortcuts, not mode switches
+    if keys.any_pressed(COMMAND_KEYS) { return; }
+
+    let new_mode = if keys.just_pressed(KeyCode::M) {
+        Mode::Move
+    } else if keys.just_pressed(KeyCode::B) {
+        Mode::AddBorderPoint
+    } else if keys.just_pressed(KeyCode::A) {
+        Mode::AddAnchor
+    } else if keys.just_pressed(KeyCode::R) {
+        Mode::AddRock
+    } else if keys.just_pressed(KeyCode::D) {
+        Mode::Deletion
+    } else {
+        return;
+    };
+
+    // leaving a rock open would make the next rock mode click add to it
+    selected_rock.open = false;
+
+    mode_text_query.single_mut().sections[1].value = new_mode.name().to_string();
+    *mode = new_mode;
+}
+
 fn add_point_on_click(
-    mut commands: Commands,
     buttons: Res<Input<MouseButton>>,
-    mut file_info: ResMut<FileInfo>,
+    mut file_info: ResMut<LevelFile>,
+    mut selected_rock: ResMut<SelectedRock>,
+    mut history: ResMut<History>,
     cursor_pos: Res<CursorPos>,
-    rock_entity_query: Query<Entity, With<PolygonPoints>>,
+    snapped_cursor_pos: Res<SnappedCursorPos>,
     mode: Res<Mode>
 ) {
     if buttons.just_pressed(MouseButton::Left) {
+        let point = [snapped_cursor_pos.pos.x, snapped_cursor_pos.pos.y];
+
         match *mode {
             Mode::AddBorderPoint => {
-                file_info.border_points.push([cursor_pos.0.x, cursor_pos.0.y]);
+                file_info.border_points.push(point);
+                history.push(Action::AddBorderPoint(file_info.border_points.len() - 1));
+            },
+            Mode::AddAnchor => {
+                file_info.anchors.push(point);
+                history.push(Action::AddAnchor(file_info.anchors.len() - 1));
+            },
+            Mode::AddRock => {
+                if let (Some(id), true) = (selected_rock.id, selected_rock.open) {
+                    if let Some(rock) = file_info.rock_mut(id) {
+                        rock.points.push(point);
+                        history.push(Action::AddRockPoint(id, rock.points.len() - 1));
 
-                if file_info.border_points.len() > 1 {
-                    if let Ok(rock_entity) = rock_entity_query.get_single() {
-                        commands.entity(rock_entity).despawn();
+                        return;
                     }
+                }
+
+                // clicking on a rock selects it, clicking anywhere else starts a new one
+                if let Some(rock) = file_info.rocks.iter().find(|rock| point_in_polygon(&rock.points(), cursor_pos.0)) {
+                    *selected_rock = SelectedRock { id: Some(rock.id), open: false };
 
-                    commands.spawn(RockBundle::new(
-                        file_info.border_points.iter().map(|[x, y]| Vec2::new(*x, *y)).collect()
-                    ));
+                    return;
                 }
+
+                let id = file_info.next_rock_id();
+
+                file_info.rocks.push(RockInfo { id, style: RockStyle::default(), points: vec![point] });
+                history.push(Action::AddRockPoint(id, 0));
+                *selected_rock = SelectedRock { id: Some(id), open: true };
             },
             _ => {}
         }
     }
 }
 
-fn undo(
-    mut commands: Commands,
-    keys: Res<Input<KeyCode>>,
-    mut file_info: ResMut<FileInfo>,
-    rock_entity_query: Query<Entity, With<PolygonPoints>>,
-    mut actions: ResMut<Actions>
+#[allow(clippy::too_many_arguments)]
+fn drag_points(
+    buttons: Res<Input<MouseButton>>,
+    mut file_info: ResMut<LevelFile>,
+    mut dragging: ResMut<Dragging>,
+    mut history: ResMut<History>,
+    mut camera_transform_query: Query<&mut Transform, With<Camera>>,
+    cursor_pos: Res<CursorPos>,
+    snapped_cursor_pos: Res<SnappedCursorPos>,
+    zoom: Res<Zoom>,
+    mode: Res<Mode>
 ) {
-    if keys.pressed(KeyCode::Z) && keys.pressed(KeyCode::LWin) {
-        match actions.0.pop().unwrap() {
-            Action::AddBorderPoint => {
-                file_info.border_points.pop();
+    // a drag that's already going keeps going until the button is released, even if the mode changes
+    if matches!(*mode, Mode::Move) && buttons.just_pressed(MouseButton::Left) {
+        let hit_radius = POINT_HIT_RADIUS * zoom.0;
+        let point_ref = find_point_near(&file_info, cursor_pos.0, hit_radius, |_| true);
+        let point_distance = point_ref
+            .and_then(|point_ref| get_point(&file_info, point_ref))
+            .map_or(f32::INFINITY, |p| p.distance(cursor_pos.0));
 
-                if let Ok(rock_entity) = rock_entity_query.get_single() {
-                    commands.entity(rock_entity).despawn();
-                }
+        let target = match point_ref {
+            _ if cursor_pos.0.length() < hit_radius.min(point_distance) => DragTarget::Start,
+            Some(point_ref) => DragTarget::Point(point_ref),
+            None => DragTarget::PlayArea
+        };
 
-                if file_info.border_points.len() > 1 {
-                    commands.spawn(RockBundle::new(
-                        file_info.border_points.iter().map(|[x, y]| Vec2::new(*x, *y)).collect()
-                    ));
-                }
+        dragging.0 = Some(Drag { target, last_cursor_pos: cursor_pos.0 });
+    }
+
+    let drag = match dragging.0.as_mut() {
+        Some(drag) => drag,
+        None => return
+    };
+
+    if drag.last_cursor_pos != cursor_pos.0 {
+        let action = match drag.target {
+            DragTarget::Point(point_ref) => get_point(&file_info, point_ref).map(|from| {
+                set_point(&mut file_info, point_ref, snapped_cursor_pos.pos);
+                move_action(point_ref, from)
+            }),
+            DragTarget::PlayArea => {
+                let offset = cursor_pos.0 - drag.last_cursor_pos;
+                move_play_area_by(&mut file_info, offset);
+
+                Some(Action::MovePlayAreaBy(offset))
             },
-            _ => {}
+            DragTarget::Start => {
+                let offset = drag.last_cursor_pos - cursor_pos.0;
+                move_play_area_by(&mut file_info, offset);
+
+                // moving the camera along with the play area makes it look like the start is
+                // what moved, and the cursor ends up that much further along in the world
+                camera_transform_query.single_mut().translation += offset.extend(0.0);
+                drag.last_cursor_pos = cursor_pos.0 + offset;
+
+                Some(Action::MovePlayAreaBy(offset))
+            }
+        };
+
+        if drag.target != DragTarget::Start {
+            drag.last_cursor_pos = cursor_pos.0;
         }
+
+        match action {
+            Some(action) => history.push_move(action),
+            // the point was undone out from under the drag
+            None => dragging.0 = None
+        }
+    }
+
+    if buttons.just_released(MouseButton::Left) {
+        history.end_move();
+        dragging.0 = None;
     }
 }
 
+fn delete_point_on_click(
+    buttons: Res<Input<MouseButton>>,
+    mut file_info: ResMut<LevelFile>,
+    mut history: ResMut<History>,
+    cursor_pos: Res<CursorPos>,
+    zoom: Res<Zoom>,
+    mode: Res<Mode>
+) {
+    if !matches!(*mode, Mode::Deletion) || !buttons.just_pressed(MouseButton::Left) { return; }
+
+    // every level needs an end, so it can't be deleted and shouldn't get in the way of points that can
+    let is_deletable = |point_ref: PointRef| !matches!(point_ref, PointRef::End);
+    let point_ref = match find_point_near(&file_info, cursor_pos.0, POINT_HIT_RADIUS * zoom.0, is_deletable) {
+        Some(point_ref) => point_ref,
+        None => return
+    };
+
+    let p = get_point(&file_info, point_ref).unwrap();
+
+    match point_ref {
+        PointRef::BorderPoint(i) => {
+            file_info.border_points.remove(i);
+            history.push(Action::DeletedBorderPoint(i, p));
+        },
+        PointRef::Anchor(i) => {
+            file_info.anchors.remove(i);
+            history.push(Action::DeletedAnchorPoint(i, p));
+        },
+        PointRef::End => unreachable!(),
+        PointRef::RockPoint(id, i) => {
+            let index = file_info.rocks.iter().position(|rock| rock.id == id).unwrap();
+
+            // deleting a rock's last point deletes the whole rock
+            if file_info.rocks[index].points.len() == 1 {
+                let rock = file_info.rocks.remove(index);
+                history.push(Action::DeletedRock(index, rock));
+            } else {
+                file_info.rocks[index].points.remove(i);
+                history.push(Action::DeletedRockPoint(id, i, p));
+            }
+        }
+    }
+}
+
+fn edit_selected_rock(
+    keys: Res<Input<KeyCode>>,
+    mode: Res<Mode>,
+    mut file_info: ResMut<LevelFile>,
+    mut selected_rock: ResMut<SelectedRock>,
+    mut history: ResMut<History>
+) {
+    // the rock keys only mean something while adding rocks, and never with cmd or ctrl held
+    if !matches!(*mode, Mode::AddRock) || keys.any_pressed(COMMAND_KEYS) { return; }
+
+    let id = match selected_rock.id {
+        Some(id) => id,
+        None => return
+    };
+
+    if keys.just_pressed(KeyCode::Return) {
+        selected_rock.open = false;
+    }
+
+    if keys.just_pressed(KeyCode::Back) || keys.just_pressed(KeyCode::Delete) {
+        if let Some(index) = file_info.rocks.iter().position(|rock| rock.id == id) {
+            let rock = file_info.rocks.remove(index);
+            history.push(Action::DeletedRock(index, rock));
+        }
+
+        *selected_rock = SelectedRock { id: None, open: false };
+    }
+
+    if keys.just_pressed(KeyCode::C) {
+        if let Some(rock) = file_info.rock_mut(id) {
+            history.push(Action::ChangedRockStyleFrom(id, rock.style));
+            rock.style = rock.style.next();
+        }
+    }
+}
+
+fn redraw_level(
+    mut commands: Commands,
+    file_info: Res<LevelFile>,
+    selected_rock: Res<SelectedRock>,
+    level_shape_query: Query<Entity, With<LevelShape>>
+) {
+    if !file_info.is_changed() && !selected_rock.is_changed() { return; }
+
+    for entity in level_shape_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if file_info.border_points.len() > 1 {
+        commands.spawn((RockBundle::new(file_info.border()), LevelShape));
+    }
+
+    for rock in file_info.rocks.iter() {
+        commands.spawn((RockBundle::with_style(rock.points(), rock.style), LevelShape));
+
+        if selected_rock.id == Some(rock.id) {
+            commands.spawn((GeometryBuilder::build_as(
+                &shapes::Polygon { points: rock.points(), closed: !selected_rock.open },
+                DrawMode::Stroke(StrokeMode::new(SELECTED_ROCK_COLOR, SELECTED_ROCK_OUTLINE_WIDTH)),
+                Transform::from_xyz(0.0, 0.0, 9.0)
+            ), LevelShape));
+        }
+    }
+
+    let mut course = vec![Vec2::ZERO];
+    course.extend(file_info.anchors());
+    course.push(file_info.end());
+
+    commands.spawn((GeometryBuilder::build_as(
+        &shapes::Polygon { points: course, closed: false },
+        DrawMode::Stroke(StrokeMode::new(COURSE_COLOR, COURSE_WIDTH)),
+        Transform::from_xyz(0.0, 0.0, 2.0)
+    ), LevelShape));
+
+    for anchor in file_info.anchors() {
+        commands.spawn((GeometryBuilder::build_as(
+            &shapes::Circle { radius: ANCHOR_RADIUS, center: anchor },
+            DrawMode::Fill(FillMode::color(ANCHOR_COLOR)),
+            Transform::from_xyz(0.0, 0.0, 3.0)
+        ), LevelShape));
+    }
+
+    // a ring so whatever is under the end is still visible
+    commands.spawn((GeometryBuilder::build_as(
+        &shapes::Circle { radius: END_RADIUS, center: file_info.end() },
+        DrawMode::Stroke(StrokeMode::new(END_COLOR, 4.0)),
+        Transform::from_xyz(0.0, 0.0, 3.0)
+    ), LevelShape));
+
+    commands.spawn((GeometryBuilder::build_as(
+        &shapes::RegularPolygon {
+            sides: 4,
+            feature: shapes::RegularPolygonFeature::Radius(START_SIZE),
+            ..shapes::RegularPolygon::default()
+        },
+        DrawMode::Fill(FillMode::color(START_COLOR)),
+        Transform::from_xyz(0.0, 0.0, 3.0)
+    ), LevelShape));
+}
+
+// cmd/ctrl + z to undo, cmd/ctrl + shift + z or cmd/ctrl + y to redo
+fn undo_redo(
+    keys: Res<Input<KeyCode>>,
+    mut file_info: ResMut<LevelFile>,
+    mut history: ResMut<History>
+) {
+    if !keys.any_pressed(COMMAND_KEYS) { return; }
+
+    let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+
+    if keys.just_pressed(KeyCode::Y) || (keys.just_pressed(KeyCode::Z) && shift) {
+        history.redo(&mut file_info);
+    } else if keys.just_pressed(KeyCode::Z) {
+        history.undo(&mut file_info);
+    }
+}
+
+fn recovery_path(path: &Path) -> PathBuf {
+    path.with_extension("json.recovery")
+}
+
+// The first name like level-1.json, level-2.json, ... that isn't taken yet
+fn copy_path(path: &Path) -> PathBuf {
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    (1..)
+        .map(|i| path.with_file_name(format!("{}-{}.json", name, i)))
+        .find(|copy_path| !copy_path.exists())
+        .unwrap()
+}
+
+// cmd + s saves the level, cmd + shift + s saves it as a copy and keeps editing the copy
 fn save_to_file(
-    mut file: ResMut<FileRef>,
-    file_info: Res<FileInfo>,
+    mut save_state: ResMut<SaveState>,
+    file_info: Res<LevelFile>,
     keys: Res<Input<KeyCode>>
+) {
+    if !keys.any_pressed(COMMAND_KEYS) || !keys.just_pressed(KeyCode::S) { return; }
+
+    let old_path = save_state.path.clone();
+    let path = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) { copy_path(&old_path) } else { old_path.clone() };
+
+    if let Err(err) = file_info.save(&path) {
+        eprintln!("{}", err);
+        return;
+    }
+
+    println!("Saved to {}", path.display());
+
+    // the changes in the recovery file are saved now
+    let _ = fs::remove_file(recovery_path(&old_path));
+
+    *save_state = SaveState { path, saved: file_info.clone() };
+}
 
+fn autosave(
+    time: Res<Time>,
+    mut autosave_timer: ResMut<AutosaveTimer>,
+    save_state: Res<SaveState>,
+    file_info: Res<LevelFile>
 ) {
-    if keys.pressed(KeyCode::S) && keys.pressed(KeyCode::LWin) {
-        let _ = file.0.rewind();
-        let _ = file.0.write(serde_json::to_string_pretty(&*file_info).unwrap().as_bytes());
-        let _ = file.0.sync_all();
+    if !autosave_timer.0.tick(time.delta()).just_finished() { return; }
+
+    if save_state.has_unsaved_changes(&file_info) {
+        if let Err(err) = file_info.save(recovery_path(&save_state.path)) {
+            eprintln!("{}", err);
+        }
+    }
+}
+
+// Puts the level's name in the title, with a * when it has unsaved changes
+fn show_unsaved_changes(
+    mut windows: ResMut<Windows>,
+    save_state: Res<SaveState>,
+    file_info: Res<LevelFile>
+) {
+    if !save_state.is_changed() && !file_info.is_changed() { return; }
+
+    let name = save_state.path.file_stem().unwrap_or_default().to_string_lossy();
+    let unsaved = if save_state.has_unsaved_changes(&file_info) { "*" } else { "" };
+
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_title(format!("{} - {}{}", WINDOW_TITLE, name, unsaved));
     }
 }
 
 fn set_cursor_pos(
     mut cursor_pos: ResMut<CursorPos>,
-    camera_transform_query: Query<&Transform, With<Camera>>,
+    mut zoom: ResMut<Zoom>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    windows: Res<Windows>
+) {
+    let window = windows.get_primary().unwrap();
+    let (camera_transform, projection) = camera_query.single();
+
+    if zoom.0 != projection.scale {
+        zoom.0 = projection.scale;
+    }
+
+    if let Some(world_pos) = cursor_to_world(window, camera_transform, projection) {
+        cursor_pos.0 = world_pos;
+    }
+}
+
+// Dragging with the right or middle mouse button moves the camera
+fn pan_camera(
+    buttons: Res<Input<MouseButton>>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+    windows: Res<Windows>,
+    mut last_window_cursor_pos: Local<Option<Vec2>>
+) {
+    let window_cursor_pos = windows.get_primary().and_then(|window| window.cursor_position());
+
+    if !buttons.any_pressed([MouseButton::Right, MouseButton::Middle]) {
+        *last_window_cursor_pos = None;
+        return;
+    }
+
+    if let (Some(last), Some(current)) = (*last_window_cursor_pos, window_cursor_pos) {
+        let (mut camera_transform, projection) = camera_query.single_mut();
+        let offset = (current - last) * projection.scale;
+
+        camera_transform.translation -= offset.extend(0.0);
+    }
+
+    *last_window_cursor_pos = window_cursor_pos;
+}
+
+// Zooms around the cursor, so whatever is under it stays there
+fn zoom_camera(
+    mut scroll_events: EventReader<MouseWheel>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    windows: Res<Windows>
+) {
+    let lines: f32 = scroll_events.iter().map(|event| match event.unit {
+        MouseScrollUnit::Line => event.y,
+        MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE
+    }).sum();
+
+    if lines == 0.0 { return; }
+
+    let window = windows.get_primary().unwrap();
+    let (mut camera_transform, mut projection) = camera_query.single_mut();
+
+    let window_cursor_pos = window.cursor_position().unwrap_or(Vec2::new(window.width(), window.height()) / 2.0);
+    let world_cursor_pos = window_to_world(window, window_cursor_pos, &camera_transform, &projection);
+
+    // scrolling up zooms in
+    projection.scale = (projection.scale * ZOOM_PER_LINE.powf(-lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+
+    let moved_cursor_pos = window_to_world(window, window_cursor_pos, &camera_transform, &projection);
+    camera_transform.translation += (world_cursor_pos - moved_cursor_pos).extend(0.0);
+}
+
+fn fit_camera_to_level(
+    keys: Res<Input<KeyCode>>,
+    file_info: Res<LevelFile>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
     windows: Res<Windows>
 ) {
+    if !keys.just_pressed(KeyCode::F) || keys.any_pressed(COMMAND_KEYS) { return; }
+
+    let points: Vec<Vec2> = all_points(&file_info).into_iter().map(|(_, p)| p).collect();
+    let min = points.iter().fold(Vec2::splat(f32::INFINITY), |min, p| min.min(*p));
+    let max = points.iter().fold(Vec2::splat(f32::NEG_INFINITY), |max, p| max.max(*p));
+
     let window = windows.get_primary().unwrap();
-    let camera_pos = camera_transform_query.single().translation;
-
-    if let Some(cursor_position) = window.cursor_position() {
-        cursor_pos.0.x = cursor_position.x - window.width() / 2.0 + camera_pos.x;
-        cursor_pos.0.y = cursor_position.y - window.height() / 2.0 + camera_pos.y;
-    }
-}
-
-fn get_file_name(choice: usize) -> String {
-    print!("\nEnter the name of the file: ");
-    std::io::Write::flush(&mut std::io::stdout()).expect("flush failed!");
-    let mut file_name = String::new();
-    let _ = std::io::stdin().read_line(&mut file_name);
-    file_name.pop();
-
-    while  
-        file_name.len() != 0 &&
-        match choice {
-            0 => { File::open(format!("src/bin/levels/{}.json", file_name)).is_ok() },
-            1 | 2 => { File::open(format!("src/bin/levels/{}.json", file_name)).is_err() },
-            _ => false
-        } 
-    {
-        print!("\nTry again: ");
-        std::io::Write::flush(&mut std::io::stdout()).expect("flush failed!");
-        file_name = String::new();
-        let _ = std::io::stdin().read_line(&mut file_name);
-        file_name.pop();
-    }
-
-    file_name
-}
-
-fn get_choice(prompt: &str, choices: Vec<&str>) -> usize {
-    println!(
-        "{}{}", 
-        prompt, 
-        choices.iter().enumerate().fold(
-            String::new(), 
-            |accum, (i, choice)| format!("{}\n   {}: {}", accum, i, choice)
+    let (mut camera_transform, mut projection) = camera_query.single_mut();
+    let size = (max - min) * FIT_MARGIN;
+
+    camera_transform.translation = ((min + max) / 2.0).extend(camera_transform.translation.z);
+    // levels smaller than the window are shown at their actual size
+    projection.scale = (size.x / window.width()).max(size.y / window.height()).clamp(1.0, MAX_ZOOM);
+}
+
+fn change_snapping(
+    keys: Res<Input<KeyCode>>,
+    mut snapping: ResMut<Snapping>
+) {
+    if keys.any_pressed(COMMAND_KEYS) { return; }
+
+    if keys.just_pressed(KeyCode::G) {
+        snapping.enabled = !snapping.enabled;
+    }
+
+    if keys.just_pressed(KeyCode::LBracket) {
+        snapping.grid_size = (snapping.grid_size / 2.0).max(MIN_GRID_SIZE);
+    }
+
+    if keys.just_pressed(KeyCode::RBracket) {
+        snapping.grid_size = (snapping.grid_size * 2.0).min(MAX_GRID_SIZE);
+    }
+}
+
+// The point the edge being placed or dragged starts from, which is what angle snapping is relative to
+fn edge_start(file_info: &LevelFile, mode: &Mode, selected_rock: &SelectedRock, dragging: &Dragging) -> Option<Vec2> {
+    let to_vec2 = |[x, y]: [f32; 2]| Vec2::new(x, y);
+    let previous = |points: &[[f32; 2]], i: usize| match points.len() {
+        0 | 1 => None,
+        n => Some(to_vec2(points[(i + n - 1) % n]))
+    };
+
+    if let Some(Drag { target: DragTarget::Point(point_ref), .. }) = dragging.0 {
+        return match point_ref {
+            PointRef::BorderPoint(i) => previous(&file_info.border_points, i),
+            PointRef::RockPoint(id, i) => previous(&file_info.rock(id)?.points, i),
+            PointRef::Anchor(_) | PointRef::End => None
+        };
+    }
+
+    match mode {
+        Mode::AddBorderPoint => file_info.border_points.last().copied().map(to_vec2),
+        Mode::AddRock if selected_rock.open => file_info.rock(selected_rock.id?)?.points.last().copied().map(to_vec2),
+        _ => None
+    }
+}
+
+// Snaps to the closest vertex if there's one under the cursor, otherwise edges snap to
+// multiples of SNAP_ANGLE and lengths of the grid size, and lone points snap to the grid
+fn snap(cursor_pos: Vec2, edge_start: Option<Vec2>, vertices: &[Vec2], grid_size: f32, vertex_radius: f32) -> SnappedCursorPos {
+    let closest_vertex = vertices
+        .iter()
+        .map(|vertex| (*vertex, vertex.distance(cursor_pos)))
+        .filter(|(_, distance)| *distance < vertex_radius)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+    if let Some((vertex, _)) = closest_vertex {
+        return SnappedCursorPos { pos: vertex, guide_from: None };
+    }
+
+    match edge_start {
+        Some(start) if start != cursor_pos => {
+            let edge = cursor_pos - start;
+            let step = SNAP_ANGLE.to_radians();
+            let angle = (edge.y.atan2(edge.x) / step).round() * step;
+            let length = ((edge.length() / grid_size).round() * grid_size).max(grid_size);
+
+            SnappedCursorPos { pos: start + length * Vec2::new(angle.cos(), angle.sin()), guide_from: Some(start) }
+        },
+        _ => SnappedCursorPos { pos: (cursor_pos / grid_size).round() * grid_size, guide_from: None }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn snap_cursor(
+    keys: Res<Input<KeyCode>>,
+    cursor_pos: Res<CursorPos>,
+    zoom: Res<Zoom>,
+    snapping: Res<Snapping>,
+    file_info: Res<LevelFile>,
+    mode: Res<Mode>,
+    selected_rock: Res<SelectedRock>,
+    dragging: Res<Dragging>,
+    mut snapped_cursor_pos: ResMut<SnappedCursorPos>
+) {
+    // holding alt flips snapping for as long as it's held
+    let enabled = snapping.enabled != keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]);
+
+    let snapped = if enabled {
+        // a dragged point shouldn't snap to itself
+        let dragged = match dragging.0 {
+            Some(Drag { target: DragTarget::Point(point_ref), .. }) => Some(point_ref),
+            _ => None
+        };
+
+        let vertices: Vec<Vec2> = all_points(&file_info)
+            .into_iter()
+            .filter(|(point_ref, _)| Some(*point_ref) != dragged)
+            .map(|(_, p)| p)
+            .collect();
+
+        snap(
+            cursor_pos.0,
+            edge_start(&file_info, &mode, &selected_rock, &dragging),
+            &vertices,
+            snapping.grid_size,
+            POINT_HIT_RADIUS * zoom.0
         )
-    );
-    
-    let mut index = String::new();
-    let _ = std::io::stdin().read_line(&mut index);
-    index.pop();
-
-    let while_conditional = |str: &String|  -> bool {
-        if let Ok(i) = str.parse::<usize>() {
-            i > choices.len()
-        } else {
-            true
-        }
+    } else {
+        SnappedCursorPos { pos: cursor_pos.0, guide_from: None }
     };
 
-    while while_conditional(&index) {
-        print!("\nTry again: ");
-        index = String::new();
-        let _ = std::io::stdin().read_line(&mut index);
-        index.pop();
+    if snapped.pos != snapped_cursor_pos.pos || snapped.guide_from != snapped_cursor_pos.guide_from {
+        *snapped_cursor_pos = snapped;
+    }
+}
+
+// Redraws the grid lines covering the window whenever the camera or the grid changes
+fn draw_grid(
+    mut commands: Commands,
+    snapping: Res<Snapping>,
+    zoom: Res<Zoom>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    windows: Res<Windows>,
+    grid_lines_query: Query<Entity, With<GridLines>>,
+    mut last_drawn: Local<Option<(Vec2, Vec2, f32)>>
+) {
+    let window = windows.get_primary().unwrap();
+    let (camera_transform, projection) = camera_query.single();
+
+    let min = window_to_world(window, Vec2::ZERO, camera_transform, projection);
+    let max = window_to_world(window, Vec2::new(window.width(), window.height()), camera_transform, projection);
+
+    let drawn = if snapping.enabled { Some((min, max, snapping.grid_size)) } else { None };
+
+    if *last_drawn == drawn { return; }
+    *last_drawn = drawn;
+
+    for entity in grid_lines_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if !snapping.enabled { return; }
+
+    // zoomed out far enough, every other line gets skipped so they don't blur together
+    let mut spacing = snapping.grid_size;
+
+    while spacing / zoom.0 < MIN_GRID_LINE_SPACING {
+        spacing *= 2.0;
+    }
+
+    let mut path_builder = PathBuilder::new();
+    let start = (min / spacing).floor() * spacing;
+
+    let mut x = start.x;
+    while x <= max.x {
+        path_builder.move_to(Vec2::new(x, min.y));
+        path_builder.line_to(Vec2::new(x, max.y));
+        x += spacing;
+    }
+
+    let mut y = start.y;
+    while y <= max.y {
+        path_builder.move_to(Vec2::new(min.x, y));
+        path_builder.line_to(Vec2::new(max.x, y));
+        y += spacing;
+    }
+
+    commands.spawn((GeometryBuilder::build_as(
+        &path_builder.build(),
+        DrawMode::Stroke(StrokeMode::new(GRID_COLOR, zoom.0)),
+        Transform::from_xyz(0.0, 0.0, 0.5)
+    ), GridLines));
+}
+
+// A marker where the next point will go, with a line along the edge when it was angle snapped
+fn draw_snap_guide(
+    mut commands: Commands,
+    snapped_cursor_pos: Res<SnappedCursorPos>,
+    zoom: Res<Zoom>,
+    snap_guide_query: Query<Entity, With<SnapGuide>>
+) {
+    if !snapped_cursor_pos.is_changed() && !zoom.is_changed() { return; }
+
+    for entity in snap_guide_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    commands.spawn((GeometryBuilder::build_as(
+        &shapes::Circle { radius: SNAP_MARKER_RADIUS * zoom.0, center: snapped_cursor_pos.pos },
+        DrawMode::Stroke(StrokeMode::new(SNAP_GUIDE_COLOR, zoom.0)),
+        Transform::from_xyz(0.0, 0.0, 10.0)
+    ), SnapGuide));
+
+    if let Some(start) = snapped_cursor_pos.guide_from {
+        commands.spawn((GeometryBuilder::build_as(
+            &shapes::Line(start, snapped_cursor_pos.pos),
+            DrawMode::Stroke(StrokeMode::new(SNAP_GUIDE_COLOR, zoom.0)),
+            Transform::from_xyz(0.0, 0.0, 10.0)
+        ), SnapGuide));
+    }
+}
+
+// p plays the level as it is right now, and p again goes back to editing it
+fn toggle_play_test(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    file_info: Res<LevelFile>,
+    mut app_state: ResMut<State<AppState>>
+) {
+    if !keys.just_pressed(KeyCode::P) || keys.any_pressed(COMMAND_KEYS) { return; }
+
+    if *app_state.current() != AppState::Editing {
+        commands.remove_resource::<Level>();
+        let _ = app_state.set(AppState::Editing);
+
+        return;
+    }
+
+    if let Err(err) = file_info.validate() {
+        eprintln!("The level can't be played yet: {}", err);
+        return;
+    }
+
+    commands.insert_resource(Level::from(file_info.clone()));
+    let _ = app_state.set(AppState::PreGame);
+}
+
+// The game draws the level itself, so the editor's drawing of it gets cleared away
+#[allow(clippy::complexity)]
+fn start_play_test(
+    mut commands: Commands,
+    mut camera_query: Query<(&Transform, &mut OrthographicProjection), With<Camera>>,
+    mut play_test_camera: ResMut<PlayTestCamera>,
+    mut clear_color: ResMut<ClearColor>,
+    mut winit_settings: ResMut<WinitSettings>,
+    mut dragging: ResMut<Dragging>,
+    mut history: ResMut<History>,
+    level_shape_query: Query<Entity, With<LevelShape>>,
+    mut editor_visibility_query: Query<&mut Visibility, Or<(With<GridLines>, With<SnapGuide>, With<ModeText>)>>
+) {
+    let (camera_transform, mut projection) = camera_query.single_mut();
+
+    play_test_camera.0 = Some((camera_transform.translation, projection.scale));
+    projection.scale = 1.0;
+
+    clear_color.0 = BACKGROUND_COLOR;
+    // the game has to keep running without any input, the editor only needs to update when there is some
+    *winit_settings = WinitSettings::game();
+
+    dragging.0 = None;
+    history.end_move();
+
+    for entity in level_shape_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    for mut visibility in editor_visibility_query.iter_mut() {
+        visibility.is_visible = false;
+    }
+}
+
+#[allow(clippy::complexity)]
+fn return_to_editing(
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    mut play_test_camera: ResMut<PlayTestCamera>,
+    mut clear_color: ResMut<ClearColor>,
+    mut winit_settings: ResMut<WinitSettings>,
+    mut file_info: ResMut<LevelFile>,
+    mut editor_visibility_query: Query<&mut Visibility, Or<(With<GridLines>, With<SnapGuide>, With<ModeText>)>>
+) {
+    // the editor starts out editing without having play-tested anything
+    let (translation, scale) = match play_test_camera.0.take() {
+        Some(camera) => camera,
+        None => return
     };
 
-    index.parse::<usize>().unwrap()
-}
\ No newline at end of file
+    let (mut camera_transform, mut projection) = camera_query.single_mut();
+
+    camera_transform.translation = translation;
+    projection.scale = scale;
+
+    clear_color.0 = Color::WHITE;
+    *winit_settings = WinitSettings::desktop_app();
+
+    for mut visibility in editor_visibility_query.iter_mut() {
+        visibility.is_visible = true;
+    }
+
+    // gets the level drawn again
+    file_info.set_changed();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(file_info: &LevelFile, point_ref: PointRef) -> Vec2 {
+        get_point(file_info, point_ref).unwrap()
+    }
+
+    // Adds a border point the way clicking in border mode does
+    fn add_border_point(file_info: &mut LevelFile, history: &mut History, p: Vec2) {
+        file_info.border_points.push([p.x, p.y]);
+        history.push(Action::AddBorderPoint(file_info.border_points.len() - 1));
+    }
+
+    // Moves a point the way each frame of a drag does
+    fn drag_point(file_info: &mut LevelFile, history: &mut History, point_ref: PointRef, to: Vec2) {
+        let from = point(file_info, point_ref);
+        set_point(file_info, point_ref, to);
+        history.push_move(move_action(point_ref, from));
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut file_info = LevelFile::default();
+        let mut history = History::new();
+
+        add_border_point(&mut file_info, &mut history, Vec2::new(1.0, 2.0));
+        add_border_point(&mut file_info, &mut history, Vec2::new(3.0, 4.0));
+
+        history.undo(&mut file_info);
+        assert_eq!(file_info.border_points, vec![[1.0, 2.0]]);
+
+        history.redo(&mut file_info);
+        assert_eq!(file_info.border_points, vec![[1.0, 2.0], [3.0, 4.0]]);
+
+        // nothing left to redo
+        history.redo(&mut file_info);
+        assert_eq!(file_info.border_points.len(), 2);
+    }
+
+    #[test]
+    fn new_action_clears_redo() {
+        let mut file_info = LevelFile::default();
+        let mut history = History::new();
+
+        add_border_point(&mut file_info, &mut history, Vec2::new(1.0, 2.0));
+        history.undo(&mut file_info);
+        assert_eq!(history.redo_stack.len(), 1);
+
+        add_border_point(&mut file_info, &mut history, Vec2::new(5.0, 6.0));
+        assert!(history.redo_stack.is_empty());
+
+        history.redo(&mut file_info);
+        assert_eq!(file_info.border_points, vec![[5.0, 6.0]]);
+    }
+
+    #[test]
+    fn history_limit_drops_the_oldest_action() {
+        let mut file_info = LevelFile::default();
+        let mut history = History::new();
+
+        for i in 0..=HISTORY_LIMIT {
+            add_border_point(&mut file_info, &mut history, Vec2::new(i as f32, 0.0));
+        }
+
+        assert_eq!(history.undo_stack.len(), HISTORY_LIMIT);
+
+        for _ in 0..=HISTORY_LIMIT {
+            history.undo(&mut file_info);
+        }
+
+        // the first point was added too long ago to be undone
+        assert_eq!(file_info.border_points, vec![[0.0, 0.0]]);
+    }
+
+    #[test]
+    fn drags_undo_all_at_once() {
+        let mut file_info = LevelFile::default();
+        let mut history = History::new();
+        let start = file_info.end();
+
+        for i in 1..=10 {
+            drag_point(&mut file_info, &mut history, PointRef::End, start + Vec2::splat(i as f32));
+        }
+        history.end_move();
+
+        assert_eq!(history.undo_stack.len(), 1);
+
+        history.undo(&mut file_info);
+        assert_eq!(file_info.end(), start);
+
+        history.redo(&mut file_info);
+        assert_eq!(file_info.end(), start + Vec2::splat(10.0));
+    }
+
+    #[test]
+    fn separate_drags_undo_separately() {
+        let mut file_info = LevelFile::default();
+        let mut history = History::new();
+        let start = file_info.end();
+
+        drag_point(&mut file_info, &mut history, PointRef::End, start + Vec2::X);
+        history.end_move();
+        drag_point(&mut file_info, &mut history, PointRef::End, start + Vec2::Y);
+        history.end_move();
+
+        assert_eq!(history.undo_stack.len(), 2);
+
+        history.undo(&mut file_info);
+        assert_eq!(file_info.end(), start + Vec2::X);
+    }
+
+    #[test]
+    fn merging_moves() {
+        let merged = Action::MovePlayAreaBy(Vec2::new(1.0, 2.0)).merge(&Action::MovePlayAreaBy(Vec2::new(3.0, 4.0)));
+        assert!(matches!(merged, Some(Action::MovePlayAreaBy(offset)) if offset == Vec2::new(4.0, 6.0)));
+
+        // the earliest position is the one to go back to
+        let merged = Action::MoveAnchorFrom(1, Vec2::ZERO).merge(&Action::MoveAnchorFrom(1, Vec2::ONE));
+        assert!(matches!(merged, Some(Action::MoveAnchorFrom(1, from)) if from == Vec2::ZERO));
+
+        assert!(Action::MoveAnchorFrom(1, Vec2::ZERO).merge(&Action::MoveAnchorFrom(2, Vec2::ZERO)).is_none());
+        assert!(Action::MoveRockPointFrom(0, 1, Vec2::ZERO).merge(&Action::MoveRockPointFrom(1, 1, Vec2::ZERO)).is_none());
+        assert!(Action::MoveEndFrom(Vec2::ZERO).merge(&Action::AddAnchor(0)).is_none());
+    }
+
+    #[test]
+    fn undo_deleting_a_rocks_last_point() {
+        let mut file_info = LevelFile::default();
+        let mut history = History::new();
+        let rock = RockInfo { id: 7, style: RockStyle::Clay, points: vec![[1.0, 2.0]] };
+        file_info.rocks.push(rock.clone());
+
+        // what deletion mode does with a rock's last point
+        let index = 0;
+        history.push(Action::DeletedRock(index, file_info.rocks.remove(index)));
+
+        history.undo(&mut file_info);
+        assert_eq!(file_info.rocks, vec![rock]);
+
+        history.redo(&mut file_info);
+        assert!(file_info.rocks.is_empty());
+    }
+
+    #[test]
+    fn undo_adding_a_rocks_first_point() {
+        let mut file_info = LevelFile::default();
+        let mut history = History::new();
+        let rock = RockInfo { id: 0, style: RockStyle::default(), points: vec![[1.0, 2.0]] };
+
+        // what rock mode does when it starts a new rock
+        file_info.rocks.push(rock.clone());
+        history.push(Action::AddRockPoint(0, 0));
+
+        history.undo(&mut file_info);
+        assert!(file_info.rocks.is_empty());
+
+        history.redo(&mut file_info);
+        assert_eq!(file_info.rocks, vec![rock]);
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use rand::prelude::{thread_rng, Rng};
+use std::{hint::black_box, time::Instant};
+
+use bevy_ier_curves::rocks_plugin::{RockGrid, PolygonBoundingBox};
+
+// Same spacing as test_rocks, so bigger levels have more rocks instead of more crowded ones
+const AREA_PER_ROCK: f32 = 200.0 * 250.0;
+const ROCK_SIZE: f32 = 140.0;
+// Roughly the area check_rock_intersection looks at each frame
+const QUERY_SIZE: f32 = 50.0;
+const NUM_QUERIES: usize = 10_000;
+
+fn main() {
+    let mut rng = thread_rng();
+
+    println!("{:>10} {:>18} {:>18}", "rocks", "linear scan (ns)", "grid (ns)");
+
+    for num_rocks in [100, 1_000, 10_000, 100_000] {
+        let level_size = (num_rocks as f32 * AREA_PER_ROCK).sqrt();
+        let mut rock_grid = RockGrid::default();
+
+        let bboxes: Vec<PolygonBoundingBox> = (0..num_rocks).map(|i| {
+            let x = rng.gen_range(0.0..level_size);
+            let y = rng.gen_range(0.0..level_size);
+            let bbox = PolygonBoundingBox(x, y + ROCK_SIZE, x + ROCK_SIZE, y);
+
+            rock_grid.insert(Entity::from_raw(i as u32), &bbox);
+
+            bbox
+        }).collect();
+
+        let queries: Vec<Vec2> = (0..NUM_QUERIES)
+            .map(|_| Vec2::new(rng.gen_range(0.0..level_size), rng.gen_range(0.0..level_size)))
+            .collect();
+
+        let start = Instant::now();
+
+        for query in queries.iter() {
+            black_box(bboxes.iter().filter(|bbox|
+                bbox.0 < query.x + QUERY_SIZE &&
+                query.y < bbox.1 &&
+                query.x < bbox.2 &&
+                bbox.3 < query.y + QUERY_SIZE
+            ).count());
+        }
+
+        let linear_time = start.elapsed().as_nanos() / NUM_QUERIES as u128;
+        let start = Instant::now();
+
+        for query in queries.iter() {
+            black_box(rock_grid.query(query.x, query.y + QUERY_SIZE, query.x + QUERY_SIZE, query.y).len());
+        }
+
+        let grid_time = start.elapsed().as_nanos() / NUM_QUERIES as u128;
+
+        println!("{:>10} {:>18} {:>18}", num_rocks, linear_time, grid_time);
+    }
+}
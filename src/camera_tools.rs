@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+// Where a point in the window is in the world, the projection's scale is how many
+// world units one pixel covers
+pub fn window_to_world(window: &Window, window_pos: Vec2, camera_transform: &Transform, projection: &OrthographicProjection) -> Vec2 {
+    let window_size = Vec2::new(window.width(), window.height());
+
+    camera_transform.translation.truncate() + (window_pos - window_size / 2.0) * projection.scale
+}
+
+pub fn cursor_to_world(window: &Window, camera_transform: &Transform, projection: &OrthographicProjection) -> Option<Vec2> {
+    Some(window_to_world(window, window.cursor_position()?, camera_transform, projection))
+}
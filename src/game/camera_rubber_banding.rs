@@ -10,7 +10,7 @@ impl Plugin for CameraRubberBandingPlugin {
         app
             .add_system_set(
                 SystemSet::on_update(AppState::InGame)
-                .with_system(rubber_band_camera)
+                .with_system(rubber_band_camera.after(GameStep::Movement))
             )
         ;
     }
@@ -12,7 +12,8 @@ const PATH_WIDTH: f32 = 1.5;
 const GHOST_PATH_LIFESPAN: Duration = Duration::from_secs(2);
 const GHOST_PATH_COLOR: Color = Color::rgb(0.8, 0.6, 0.6);
 
-const T_INCREMENT: fn(f32) -> f32 = |time| 0.1 + 0.01 * time;
+// How fast the player moves in world units per second, a typical curve used to take ten seconds at the start
+const SPEED: fn(f32) -> f32 = |time| (0.1 + 0.01 * time) * NEW_POINT_GEN_RADIUS as f32;
 
 pub struct CurveMovementPlugin;
 
@@ -25,8 +26,8 @@ impl Plugin for CurveMovementPlugin {
             )
             .add_system_set(
                 SystemSet::on_update(AppState::InGame)
-                .with_system(increment_t)
-                .with_system(update_movement)
+                .with_system(increment_t.label(GameStep::Timing).after(GameStep::Curve))
+                .with_system(update_movement.label(GameStep::Movement).after(GameStep::Timing))
             );
     }
 }
@@ -39,6 +40,7 @@ pub struct CurvePath;
 #[derive(Component)]
 pub struct LastPoint;
 
+#[allow(clippy::too_many_arguments)]
 fn setup_curve(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -46,12 +48,24 @@ fn setup_curve(
     mut current_curve: ResMut<CurrentCurve>,
     control_points: Res<ControlPoints>,
     next_point_pos: ResMut<NextPointPos>,
-    cursor_pos: Res<CursorPos>,
-    next_point_transform_query: Query<&mut Transform, With<NextPoint>>
+    player_input: Res<PlayerInput>,
+    next_point_transform_query: Query<&mut Transform, With<NextPoint>>,
+    level: Option<Res<Level>>,
+    mut level_progress: ResMut<LevelProgress>,
+    mut game_rng: ResMut<GameRng>
 ) {
     let mut last_point_transform = Transform::from_xyz(0.0, 0.0, 2.9);
 
-    reset_current_curve(&mut current_curve, &mut last_point_transform.translation, control_points, next_point_pos, cursor_pos, next_point_transform_query);
+    reset_current_curve(
+        &mut current_curve, 
+        &mut last_point_transform.translation, 
+        control_points, 
+        next_point_pos, 
+        player_input, 
+        next_point_transform_query,
+        level_progress.take_course_point(&level),
+        &mut game_rng
+    );
 
     commands.spawn((GeometryBuilder::build_as(
         &current_curve.0.as_ref().unwrap().to_bezier_path(), 
@@ -72,19 +86,29 @@ fn setup_curve(
 fn increment_t(
     time: Res<Time>,
     mut t: ResMut<T>,
-    start_game_time: Res<StartGameTime>,
+    mut distance: ResMut<Distance>,
+    mut game_time: ResMut<GameTime>,
     mut current_curve: ResMut<CurrentCurve>,
     mut last_point_query: Query<&mut Transform, (With<LastPoint>, Without<NextPoint>)>,
     control_points: Res<ControlPoints>,
     next_point_pos: ResMut<NextPointPos>,
-    cursor_pos: Res<CursorPos>,
+    player_input: Res<PlayerInput>,
     next_point_transform_query: Query<&mut Transform, With<NextPoint>>,
+    level: Option<Res<Level>>,
+    mut level_progress: ResMut<LevelProgress>,
+    mut game_rng: ResMut<GameRng>,
+    mut score: ResMut<Score>,
     mut commands: Commands
 ) {
-    t.0 += T_INCREMENT(time.elapsed_seconds() - start_game_time.0) / 60.0;
+    // moving a set distance each frame keeps the speed the same no matter how long the curve is
+    distance.0 += SPEED(game_time.0) * TIMESTEP;
+    game_time.0 += TIMESTEP;
+    t.0 = distance.0 / current_curve.0.as_ref().unwrap().length() as f32;
     
     if t.0 >= 1.0 {
         t.0 = 0.0;
+        distance.0 = 0.0;
+        score.0 += 1;
 
         commands.spawn((GeometryBuilder::build_as(
             &current_curve.0.as_ref().unwrap().to_bezier_path(), 
@@ -100,8 +124,10 @@ fn increment_t(
             &mut last_point_query.single_mut().translation, 
             control_points, 
             next_point_pos, 
-            cursor_pos, 
-            next_point_transform_query
+            player_input, 
+            next_point_transform_query,
+            level_progress.take_course_point(&level),
+            &mut game_rng
         );
     }
 }
@@ -112,7 +138,8 @@ fn update_movement(
     mut player_transform_query: Query<&mut Transform, With<Player>>,
     mut curve_path_query: Query<&mut Path, With<CurvePath>>
 ) {
-    let new_player_pos = current_curve.0.as_ref().unwrap().get_point(t.0 as f64);
+    let curve = current_curve.0.as_ref().unwrap();
+    let new_player_pos = curve.point_at_distance(t.0 as f64 * curve.length());
     let player_pos = &mut player_transform_query.single_mut().translation;
 
     player_pos.x = new_player_pos.x as f32;
@@ -121,31 +148,38 @@ fn update_movement(
     *curve_path_query.single_mut() = current_curve.0.as_ref().unwrap().to_bezier_path();
 }
 
+#[allow(clippy::too_many_arguments)]
 fn reset_current_curve(
     current_curve: &mut ResMut<CurrentCurve>,
     last_point_pos: &mut Vec3,
     control_points: Res<ControlPoints>,
     mut next_point_pos: ResMut<NextPointPos>,
-    cursor_pos: Res<CursorPos>,
-    mut next_point_transform_query: Query<&mut Transform, With<NextPoint>>
+    player_input: Res<PlayerInput>,
+    mut next_point_transform_query: Query<&mut Transform, With<NextPoint>>,
+    course_point: Option<Vec2>,
+    game_rng: &mut GameRng
 ) {
     let last_point = next_point_pos.0;
 
     last_point_pos.x = last_point.x as f32;
     last_point_pos.y = last_point.y as f32;
 
-    let mut facing_dir = ((last_point.y - control_points.1.y) / (last_point.x - control_points.1.x)).atan();
-
-    facing_dir += if last_point.x > control_points.1.x { PI } else { 0.0 };
-
-    let mut rng = thread_rng();
-    let angle = rng.gen_range((facing_dir - ANGLE_SPREAD)..(facing_dir + ANGLE_SPREAD));
-    let dist = rng.gen_range(0.0..NEW_POINT_GEN_RADIUS.powf(DISTRIBUTION)).powf(1.0 / DISTRIBUTION);
-    
-    next_point_pos.0 += Vector3::new(dist * angle.cos(), dist * angle.sin(), 0.0);
+    // Head the way the ghost handle points, the next curve leaves along it even while Q or W hold it
+    let facing = control_points.1 - last_point;
+    let facing_dir = facing.y.atan2(facing.x);
+
+    // levels decide where the curve goes, otherwise it's random
+    if let Some(course_point) = course_point {
+        next_point_pos.0 = Vector3::new(course_point.x as f64, course_point.y as f64, 0.0);
+    } else {
+        let angle = game_rng.gen_range((facing_dir - ANGLE_SPREAD)..(facing_dir + ANGLE_SPREAD));
+        let dist = game_rng.gen_range(0.0..NEW_POINT_GEN_RADIUS.powf(DISTRIBUTION)).powf(1.0 / DISTRIBUTION);
+        
+        next_point_pos.0 += Vector3::new(dist * angle.cos(), dist * angle.sin(), 0.0);
+    }
 
     let next_handle = 
-        2.0 * next_point_pos.0 - Vector3::new(cursor_pos.0.x as f64, cursor_pos.0.y as f64, 0.0);
+        2.0 * next_point_pos.0 - Vector3::new(player_input.cursor_pos.x as f64, player_input.cursor_pos.y as f64, 0.0);
 
     current_curve.0 = Some(Curve::new_bezier(
         last_point, 
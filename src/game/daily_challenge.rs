@@ -0,0 +1,197 @@
+use super::*;
+use std::{collections::BTreeMap, fs, time::{SystemTime, UNIX_EPOCH}};
+
+pub const DAILY_SCORES_PATH: &str = "daily_scores.json";
+// How many of the best scores are kept for each day
+const DAILY_TABLE_SIZE: usize = 10;
+
+const DAILY_TABLE_FONT_SIZE: f32 = 30.0;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+pub struct DailyChallengePlugin;
+
+impl Plugin for DailyChallengePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(
+                SystemSet::on_enter(AppState::PostGame)
+                .with_system(record_daily_score)
+            )
+        ;
+    }
+}
+
+// Everyone playing on the same day (in UTC) gets the same seed, so they all get the same course
+#[derive(Resource)]
+pub struct DailyChallenge {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32
+}
+
+impl DailyChallenge {
+    pub fn today() -> Self {
+        let days = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_secs() / SECONDS_PER_DAY);
+        let (year, month, day) = date_from_days(days as i64);
+
+        DailyChallenge { year, month, day }
+    }
+
+    pub fn date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    // The date as a number, 2023-01-31 is 20230131
+    pub fn seed(&self) -> u64 {
+        self.year as u64 * 10000 + self.month as u64 * 100 + self.day as u64
+    }
+}
+
+// Turns days since 1970-01-01 into (year, month, day), see
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn date_from_days(days: i64) -> (i64, u32, u32) {
+    // counting from 0000-03-01 puts leap days at the end of the year
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month as u32, day as u32)
+}
+
+// The best scores for each date, best first
+type DailyScores = BTreeMap<String, Vec<u32>>;
+
+fn load_daily_scores() -> Result<DailyScores, String> {
+    match fs::read_to_string(DAILY_SCORES_PATH) {
+        Ok(json) => serde_json::from_str(&json).map_err(|err| err.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(DailyScores::new()),
+        Err(err) => Err(err.to_string())
+    }
+}
+
+// Puts the score in with the best scores and returns where it ranks, a tie goes
+// below the score that got there first
+fn add_score(best_scores: &mut Vec<u32>, score: u32) -> usize {
+    let rank = best_scores.partition_point(|&best_score| best_score >= score);
+
+    best_scores.insert(rank, score);
+    best_scores.truncate(DAILY_TABLE_SIZE);
+
+    rank
+}
+
+fn save_daily_scores(daily_scores: &DailyScores) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(daily_scores).map_err(|err| err.to_string())?;
+
+    fs::write(DAILY_SCORES_PATH, json).map_err(|err| err.to_string())
+}
+
+fn record_daily_score(
+    mut commands: Commands,
+    daily_challenge: Option<Res<DailyChallenge>>,
+    score: Res<Score>,
+    asset_server: Res<AssetServer>
+) {
+    let daily_challenge = match daily_challenge {
+        Some(daily_challenge) => daily_challenge,
+        None => return
+    };
+    let date = daily_challenge.date();
+
+    // a broken score file gets left alone instead of being overwritten
+    let mut daily_scores = match load_daily_scores() {
+        Ok(daily_scores) => daily_scores,
+        Err(err) => {
+            eprintln!("Could not load the daily scores from {}: {}", DAILY_SCORES_PATH, err);
+            return;
+        }
+    };
+
+    let best_scores = daily_scores.entry(date.clone()).or_default();
+    let rank = add_score(best_scores, score.0);
+
+    let mut table = format!("// Daily challenge {}", date);
+    for (i, best_score) in best_scores.iter().enumerate() {
+        table += &format!("\n{}. {}{}", i + 1, best_score, if i == rank { " <" } else { "" });
+    }
+
+    if let Err(err) = save_daily_scores(&daily_scores) {
+        eprintln!("Could not save the daily scores to {}: {}", DAILY_SCORES_PATH, err);
+    }
+
+    commands.spawn((TextBundle::from_section(
+        table,
+        TextStyle {
+            font: asset_server.load("fonts/HankenGrotesk-VariableFont_wght.ttf"),
+            font_size: DAILY_TABLE_FONT_SIZE,
+            color: Color::BLACK
+        }
+    )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(40.0),
+                left: Val::Px(45.0),
+                ..default()
+            },
+            ..default()
+        }), EndScreenStuff
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(date_from_days(0), (1970, 1, 1));
+        assert_eq!(date_from_days(19722), (2023, 12, 31));
+        assert_eq!(date_from_days(19782), (2024, 2, 29));
+        assert_eq!(date_from_days(19783), (2024, 3, 1));
+        // centuries only get a leap day every 400 years
+        assert_eq!(date_from_days(11016), (2000, 2, 29));
+        assert_eq!(date_from_days(47541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn seeds() {
+        let daily_challenge = DailyChallenge { year: 2024, month: 2, day: 29 };
+
+        assert_eq!(daily_challenge.date(), "2024-02-29");
+        assert_eq!(daily_challenge.seed(), 20240229);
+    }
+
+    #[test]
+    fn ranking_scores() {
+        let mut best_scores = vec![10, 7, 7, 3];
+
+        // ties go below the scores already there
+        assert_eq!(add_score(&mut best_scores, 7), 3);
+        assert_eq!(add_score(&mut best_scores, 11), 0);
+        assert_eq!(add_score(&mut best_scores, 0), 6);
+        assert_eq!(best_scores, vec![11, 10, 7, 7, 7, 3, 0]);
+    }
+
+    #[test]
+    fn ranking_past_the_table() {
+        let mut best_scores: Vec<u32> = (1..=DAILY_TABLE_SIZE as u32).rev().collect();
+
+        assert_eq!(add_score(&mut best_scores, 0), DAILY_TABLE_SIZE);
+        assert_eq!(best_scores.len(), DAILY_TABLE_SIZE);
+        assert!(!best_scores.contains(&0));
+
+        assert_eq!(add_score(&mut best_scores, 100), 0);
+        assert_eq!(best_scores.len(), DAILY_TABLE_SIZE);
+        assert_eq!(best_scores.last(), Some(&2));
+    }
+}
@@ -15,12 +15,12 @@ impl Plugin for HandlePlugin {
             .add_startup_system(setup_handles)
             .add_system_set(
                 SystemSet::on_update(AppState::PreGame)
-                .with_system(place_handle_at_cursor)
+                .with_system(place_handle_at_cursor.label(GameStep::Handles))
             )
             .add_system_set(
                 SystemSet::on_update(AppState::InGame)
-                .with_system(place_handle_at_cursor)
-                .with_system(set_curve)
+                .with_system(place_handle_at_cursor.label(GameStep::Handles))
+                .with_system(set_curve.label(GameStep::Curve).after(GameStep::Handles))
             )
         ;
     }
@@ -47,7 +47,7 @@ pub fn make_handle(
         &get_line_path(base, head), 
         DrawMode::Stroke(StrokeMode::new(color, HANDLE_LINE_WIDTH)), 
         Transform::from_xyz(0.0, 0.0, z)
-    ), component));
+    ), component, GameEntity));
 
     // outer circle
     commands.spawn((MaterialMesh2dBundle {
@@ -57,7 +57,7 @@ pub fn make_handle(
             ::from_xyz(head.x, head.y, z)
             .with_scale(Vec3::new(HANDLE_OUTER_CIRCLE_RADIUS, HANDLE_OUTER_CIRCLE_RADIUS, HANDLE_OUTER_CIRCLE_RADIUS)),
         ..default()
-    }, component));
+    }, component, GameEntity));
 
     // inner circle
     commands.spawn((MaterialMesh2dBundle {
@@ -67,7 +67,7 @@ pub fn make_handle(
             ::from_xyz(head.x, head.y, z + 0.01)
             .with_scale(Vec3::new(HANDLE_INNER_CIRCLE_RADIUS, HANDLE_INNER_CIRCLE_RADIUS, HANDLE_INNER_CIRCLE_RADIUS)),
         ..default()
-    }, component));
+    }, component, GameEntity));
 }
 
 fn setup_handles(
@@ -108,12 +108,11 @@ fn place_handle_at_cursor(
     mut ghost_handle_transform_query: Query<&mut Transform, (With<GhostHandle>, Without<Path>)>,
     next_point_query: Res<NextPointPos>,
     mut control_points_query: ResMut<ControlPoints>,
-    cursor_pos: Res<CursorPos>,
-    keys: Res<Input<KeyCode>>
+    player_input: Res<PlayerInput>
 ) {
     let next_point = Vec2::new(next_point_query.0.x as f32, next_point_query.0.y as f32);
-    let cursor_x = cursor_pos.0.x;
-    let cursor_y = cursor_pos.0.y;
+    let cursor_x = player_input.cursor_pos.x;
+    let cursor_y = player_input.cursor_pos.y;
 
     control_points_query.0 = Vector3::new(
         cursor_x as f64,
@@ -131,13 +130,13 @@ fn place_handle_at_cursor(
         cursor_handle_transform.translation.y = cursor_y;
     }
 
-    let ghost_x = if keys.pressed(KeyCode::Q) {
+    let ghost_x = if player_input.hold_x {
         cursor_x
     } else {
         2.0 * next_point.x - cursor_x
     };
 
-    let ghost_y = if keys.pressed(KeyCode::W) {
+    let ghost_y = if player_input.hold_y {
         cursor_y
     } else {
         2.0 * next_point.y - cursor_y
@@ -0,0 +1,199 @@
+use super::*;
+use crate::level_format::*;
+
+const BORDER_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+const BORDER_WIDTH: f32 = 3.0;
+
+const ANCHOR_COLOR: Color = Color::rgb(0.3, 0.3, 0.6);
+const ANCHOR_RADIUS: f32 = 5.0;
+
+const END_COLOR: Color = Color::rgb(0.2, 0.6, 0.2);
+const END_RADIUS: f32 = 15.0;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(LevelProgress { next_point: 0, complete: false })
+            .add_system(spawn_level)
+            .add_system_set(
+                SystemSet::on_enter(AppState::PreGame)
+                .with_system(reset_level_progress)
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                .with_system(check_level_border.label(GameStep::Border).after(GameStep::Rocks))
+                .with_system(check_level_end.label(GameStep::End).after(GameStep::Border))
+            )
+        ;
+    }
+}
+
+// The player has to stay inside the border, avoid the rocks and go through
+// each anchor in order, the level is complete once they reach the end
+#[derive(Resource)]
+pub struct Level {
+    pub border: Vec<Vec2>,
+    pub anchors: Vec<Vec2>,
+    pub end: Vec2,
+    pub rocks: Vec<RockInfo>
+}
+
+impl From<LevelFile> for Level {
+    fn from(level_file: LevelFile) -> Self {
+        Level {
+            border: level_file.border(),
+            anchors: level_file.anchors(),
+            end: level_file.end(),
+            rocks: level_file.rocks
+        }
+    }
+}
+
+impl Level {
+    pub fn load(name: &str) -> Result<Self, LevelError> {
+        let level_file = LevelFile::load(format!("{}/{}.json", LEVELS_DIR, name))?;
+
+        level_file.validate()?;
+
+        Ok(Level::from(level_file))
+    }
+
+    // The points the curve goes through, the anchors and then the end
+    pub fn course_point(&self, index: usize) -> Option<Vec2> {
+        match index {
+            i if i < self.anchors.len() => Some(self.anchors[i]),
+            i if i == self.anchors.len() => Some(self.end),
+            _ => None
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct LevelProgress {
+    // index of the next course point the curve will go to
+    pub next_point: usize,
+    pub complete: bool
+}
+
+impl LevelProgress {
+    // The next point the level wants the curve to go to, if a level is being played
+    pub fn take_course_point(&mut self, level: &Option<Res<Level>>) -> Option<Vec2> {
+        let point = level.as_ref()?.course_point(self.next_point)?;
+        self.next_point += 1;
+
+        Some(point)
+    }
+}
+
+// Everything drawn for the level, which gets replaced whenever the level does
+#[derive(Component)]
+struct LevelEntity;
+
+fn spawn_level(
+    mut commands: Commands,
+    level: Option<Res<Level>>,
+    level_entity_query: Query<Entity, With<LevelEntity>>
+) {
+    // the level editor takes the level away once it's done play-testing it
+    let changed = match level.as_ref() {
+        Some(level) => level.is_changed(),
+        None => !level_entity_query.is_empty()
+    };
+
+    if !changed { return; }
+
+    for entity in level_entity_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let level = match level {
+        Some(level) => level,
+        None => return
+    };
+
+    if level.border.len() > 2 {
+        commands.spawn((GeometryBuilder::build_as(
+            &shapes::Polygon { points: level.border.clone(), closed: true },
+            DrawMode::Stroke(StrokeMode::new(BORDER_COLOR, BORDER_WIDTH)),
+            Transform::from_xyz(0.0, 0.0, 1.5)
+        ), LevelEntity));
+    }
+
+    for rock in level.rocks.iter() {
+        commands.spawn((RockBundle::with_style(rock.points(), rock.style), LevelEntity));
+    }
+
+    for anchor in level.anchors.iter() {
+        commands.spawn((GeometryBuilder::build_as(
+            &shapes::Circle { radius: ANCHOR_RADIUS, center: *anchor },
+            DrawMode::Fill(FillMode::color(ANCHOR_COLOR)),
+            Transform::from_xyz(0.0, 0.0, 1.5)
+        ), LevelEntity));
+    }
+
+    commands.spawn((GeometryBuilder::build_as(
+        &shapes::Circle { radius: END_RADIUS, center: level.end },
+        DrawMode::Fill(FillMode::color(END_COLOR)),
+        Transform::from_xyz(0.0, 0.0, 1.5)
+    ), LevelEntity));
+}
+
+fn reset_level_progress(
+    mut level_progress: ResMut<LevelProgress>
+) {
+    level_progress.next_point = 0;
+    level_progress.complete = false;
+}
+
+fn check_level_border(
+    level: Option<Res<Level>>,
+    player_query: Query<(&Transform, Option<&Hitbox>), With<Player>>,
+    mut last_contact: ResMut<LastContact>,
+    mut app_state: ResMut<State<AppState>>
+) {
+    let level = match level {
+        Some(level) if level.border.len() > 2 => level,
+        _ => return
+    };
+
+    let (player_transform, hitbox) = player_query.single();
+    let player_pos = Vec2::new(player_transform.translation.x, player_transform.translation.y);
+    let radius = hitbox.map_or(PLAYER_RADIUS, |hitbox| hitbox.radius);
+
+    let contact = circle_border_contact(&level.border, player_pos, radius);
+
+    if contact.is_some() {
+        last_contact.0 = contact;
+        let _ = app_state.set(AppState::PostGame);
+    }
+}
+
+fn check_level_end(
+    level: Option<Res<Level>>,
+    player_query: Query<(&Transform, Option<&Hitbox>), With<Player>>,
+    last_contact: Res<LastContact>,
+    mut level_progress: ResMut<LevelProgress>,
+    mut app_state: ResMut<State<AppState>>
+) {
+    let level = match level {
+        Some(level) => level,
+        None => return
+    };
+
+    // hitting a rock or the border on the same frame still loses
+    if last_contact.0.is_some() { return; }
+
+    // the end only counts once every anchor has been gone through and the curve is heading for it
+    if level_progress.next_point <= level.anchors.len() { return; }
+
+    let (player_transform, hitbox) = player_query.single();
+    let player_pos = Vec2::new(player_transform.translation.x, player_transform.translation.y);
+    let radius = hitbox.map_or(PLAYER_RADIUS, |hitbox| hitbox.radius);
+
+    // set fails if something else already ended the game this frame
+    if player_pos.distance(level.end) < radius + END_RADIUS && app_state.set(AppState::PostGame).is_ok() {
+        level_progress.complete = true;
+    }
+}
@@ -0,0 +1,224 @@
+// The game itself, as a plugin so the level editor can play-test levels with it
+
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle, input::InputSystem};
+use bevy_prototype_lyon::prelude::*;
+use nalgebra::Vector3;
+use crate::{camera_tools::cursor_to_world, game_rng::GameRng, geometry::*, rocks_plugin::*, spline::*};
+
+mod handle_plugin;
+use handle_plugin::*;
+
+mod curve_movement;
+use curve_movement::*;
+
+mod state_control;
+use state_control::*;
+
+mod camera_rubber_banding;
+use camera_rubber_banding::*;
+
+mod lifetime_plugin;
+use lifetime_plugin::*;
+
+mod point_tools;
+use point_tools::*;
+
+mod daily_challenge;
+pub use daily_challenge::DailyChallenge;
+use daily_challenge::*;
+
+mod replay;
+pub use replay::{Recording, Replay, ReplayFile};
+use replay::*;
+
+mod level_plugin;
+pub use level_plugin::Level;
+use level_plugin::*;
+
+pub const BACKGROUND_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
+
+pub const POINT_COLOR: Color = Color::rgb(0.5, 0.3, 0.3);
+pub const POINT_RADIUS: f32 = 7.0;
+
+const PLAYER_COLOR: Color = Color::RED;
+const PLAYER_RADIUS: f32 = 10.0;
+
+// How much game time passes each frame
+const TIMESTEP: f32 = 1.0 / 60.0;
+
+// How far along the current curve the player is, from 0 to 1
+#[derive(Resource)]
+struct T(f32);
+
+// How far the player has moved along the current curve in world units
+#[derive(Resource)]
+struct Distance(f32);
+
+// Seconds since the game started, it goes up by TIMESTEP every frame instead of
+// following the clock so a replay plays out exactly the same
+#[derive(Resource)]
+struct GameTime(f32);
+
+#[derive(Resource)]
+struct NextPointPos(Vector3<f64>);
+
+#[derive(Resource)]
+struct ControlPoints(Vector3<f64>, Vector3<f64>);
+
+#[derive(Resource)]
+struct CurrentCurve(Option<Curve>);
+
+// What the player is doing this frame, the game only looks at this and not the mouse
+// and keyboard so a replay can take the player's place
+#[derive(Resource, Clone, Copy, Default)]
+struct PlayerInput {
+    cursor_pos: Vec2,
+    // holding Q or W keeps the ghost handle on the cursor's side horizontally or vertically
+    hold_x: bool,
+    hold_y: bool,
+    click: bool
+}
+
+// How many points the player has reached this game
+#[derive(Resource)]
+struct Score(u32);
+
+// Where the player hit a rock, if that's how the last game ended
+#[derive(Resource)]
+struct LastContact(Option<Contact>);
+
+// The game never goes into Editing on its own, it's there so the level editor
+// can pause the game while the level is being edited
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AppState {
+    Editing,
+    PreGame,
+    InGame,
+    PostGame
+}
+
+// The order things happen in each frame of a game, otherwise bevy can pick a different
+// order every frame and a replay might not end the same way
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum GameStep {
+    Handles,
+    Curve,
+    Timing,
+    Movement,
+    Rocks,
+    Border,
+    End
+}
+
+// Getting a new game ready as PreGame starts, anything made from the game's seed goes after it
+#[derive(SystemLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NewGame;
+
+pub struct GamePlugin {
+    pub initial_state: AppState,
+    // every game gets a random one if there isn't one
+    pub seed: Option<u64>
+}
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(T(0.0))
+            .insert_resource(Distance(0.0))
+            .insert_resource(GameTime(0.0))
+            .insert_resource(NextPointPos(Vector3::new(0.0, 0.0, 0.0)))
+            .insert_resource(ControlPoints(Vector3::zeros(), Vector3::zeros()))
+            .insert_resource(CurrentCurve(None))
+            .insert_resource(PlayerInput::default())
+            .insert_resource(LastContact(None))
+            .insert_resource(Score(0))
+            .insert_resource(self.seed.map_or_else(GameRng::unseeded, GameRng::new))
+            .add_state(self.initial_state.clone())
+
+            .add_plugin(HandlePlugin)
+            .add_plugin(CurveMovementPlugin)
+            .add_plugin(StateControlPlugin)
+            .add_plugin(CameraRubberBandingPlugin)
+            .add_plugin(LifetimePlugin)
+            .add_plugin(RocksPlugin)
+            .add_plugin(LevelPlugin)
+            .add_plugin(DailyChallengePlugin)
+            .add_plugin(ReplayPlugin)
+
+            .add_startup_system(setup)
+            .add_system_to_stage(CoreStage::PreUpdate, read_player_input.after(InputSystem))
+        ;
+    }
+}
+
+#[derive(Component)]
+struct NextPoint;
+
+#[derive(Component)]
+struct Player;
+
+// The circle around the player that collides with rocks, players without one use PLAYER_RADIUS
+#[derive(Component)]
+struct Hitbox {
+    radius: f32
+}
+
+// Everything that sticks around between games, which gets hidden while editing
+#[derive(Component, Clone, Copy)]
+struct GameEntity;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>
+) {
+    // commands.spawn(SpriteBundle {
+    //     sprite: Sprite {
+    //         color: Color::rgba(1.0, 1.0, 1.0, 0.1),
+    //         custom_size: Some(Vec2::new(window.width(), window.height())),
+    //         ..default()
+    //     },
+    //     transform: Transform
+    //         ::from_xyz(0.0, 0.0, 0.0),
+    //     ..default()
+    // });
+
+    spawn_point(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        POINT_COLOR,
+        POINT_RADIUS,
+        Transform::from_xyz(0.0, 0.0, 2.9),
+        (NextPoint, GameEntity)
+    );
+
+    spawn_point(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        PLAYER_COLOR,
+        PLAYER_RADIUS,
+        Transform::from_xyz(0.0, 0.0, 3.0),
+        (Player, Hitbox { radius: PLAYER_RADIUS }, GameEntity)
+    );
+}
+
+fn read_player_input(
+    mut player_input: ResMut<PlayerInput>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    windows: Res<Windows>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>
+) {
+    let window = windows.get_primary().unwrap();
+    let (camera_transform, projection) = camera_query.single();
+
+    if let Some(world_pos) = cursor_to_world(window, camera_transform, projection) {
+        player_input.cursor_pos = world_pos;
+    }
+
+    player_input.hold_x = keys.pressed(KeyCode::Q);
+    player_input.hold_y = keys.pressed(KeyCode::W);
+    player_input.click = buttons.just_pressed(MouseButton::Left);
+}
@@ -7,7 +7,7 @@ pub fn spawn_point(
     color: Color,
     radius: f32,
     transform: Transform,
-    component: impl Component
+    component: impl Bundle
 ) {
     commands.spawn((MaterialMesh2dBundle {
         mesh: meshes.add(shape::Circle::default().into()).into(),
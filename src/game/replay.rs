@@ -0,0 +1,183 @@
+use super::*;
+use serde::{Serialize, Deserialize};
+use std::{fs, io, path::{Path, PathBuf}};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_to_stage(CoreStage::PreUpdate, replay_input.after(read_player_input))
+            .add_system_to_stage(CoreStage::PreUpdate, record_input.after(replay_input))
+            .add_system_set(
+                SystemSet::on_enter(AppState::PreGame)
+                .with_system(restart_replay)
+                .with_system(start_recording)
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::PostGame)
+                .with_system(save_recording)
+            )
+        ;
+    }
+}
+
+// The player's input for one frame of PreGame or InGame
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct ReplayFrame {
+    cursor_pos: [f32; 2],
+    hold_x: bool,
+    hold_y: bool,
+    click: bool
+}
+
+impl From<PlayerInput> for ReplayFrame {
+    fn from(player_input: PlayerInput) -> Self {
+        ReplayFrame {
+            cursor_pos: player_input.cursor_pos.into(),
+            hold_x: player_input.hold_x,
+            hold_y: player_input.hold_y,
+            click: player_input.click
+        }
+    }
+}
+
+impl From<ReplayFrame> for PlayerInput {
+    fn from(frame: ReplayFrame) -> Self {
+        PlayerInput {
+            cursor_pos: frame.cursor_pos.into(),
+            hold_x: frame.hold_x,
+            hold_y: frame.hold_y,
+            click: frame.click
+        }
+    }
+}
+
+// Everything needed to play a game again exactly the way it went, the seed decides the
+// rocks and points and the frames are everything the player did
+#[derive(Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub seed: u64,
+    // the level's name, if it was played on one
+    pub level: Option<String>,
+    frames: Vec<ReplayFrame>
+}
+
+impl ReplayFile {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_string(self)?)
+    }
+}
+
+// Feeds a replay file to the game instead of the player, starting over every game
+#[derive(Resource)]
+pub struct Replay {
+    frames: Vec<ReplayFrame>,
+    next_frame: usize
+}
+
+impl From<ReplayFile> for Replay {
+    fn from(replay_file: ReplayFile) -> Self {
+        Replay {
+            frames: replay_file.frames,
+            next_frame: 0
+        }
+    }
+}
+
+// Saves every game to a replay file when it ends, so the file always has the last game
+#[derive(Resource)]
+pub struct Recording {
+    path: PathBuf,
+    level: Option<String>,
+    frames: Vec<ReplayFrame>
+}
+
+impl Recording {
+    pub fn new(path: impl Into<PathBuf>, level: Option<String>) -> Self {
+        Recording {
+            path: path.into(),
+            level,
+            frames: Vec::new()
+        }
+    }
+}
+
+// Only PreGame and InGame get recorded, nothing the player does in the other states changes the game
+fn is_playing(app_state: &State<AppState>) -> bool {
+    matches!(app_state.current(), AppState::PreGame | AppState::InGame)
+}
+
+fn replay_input(
+    replay: Option<ResMut<Replay>>,
+    app_state: Res<State<AppState>>,
+    mut player_input: ResMut<PlayerInput>
+) {
+    let mut replay = match replay {
+        Some(replay) if is_playing(&app_state) => replay,
+        _ => return
+    };
+
+    let frame = replay.frames.get(replay.next_frame).copied();
+
+    match frame {
+        Some(frame) => {
+            *player_input = PlayerInput::from(frame);
+            replay.next_frame += 1;
+        },
+        // once the replay runs out the player just stays where they were
+        None => *player_input = PlayerInput { click: false, ..*player_input }
+    }
+}
+
+fn record_input(
+    recording: Option<ResMut<Recording>>,
+    app_state: Res<State<AppState>>,
+    player_input: Res<PlayerInput>
+) {
+    if let Some(mut recording) = recording {
+        if is_playing(&app_state) {
+            recording.frames.push(ReplayFrame::from(*player_input));
+        }
+    }
+}
+
+fn restart_replay(
+    replay: Option<ResMut<Replay>>
+) {
+    if let Some(mut replay) = replay {
+        replay.next_frame = 0;
+    }
+}
+
+fn start_recording(
+    recording: Option<ResMut<Recording>>
+) {
+    if let Some(mut recording) = recording {
+        recording.frames.clear();
+    }
+}
+
+fn save_recording(
+    recording: Option<ResMut<Recording>>,
+    game_rng: Res<GameRng>
+) {
+    let mut recording = match recording {
+        Some(recording) => recording,
+        None => return
+    };
+
+    let replay_file = ReplayFile {
+        seed: game_rng.seed(),
+        level: recording.level.clone(),
+        frames: std::mem::take(&mut recording.frames)
+    };
+
+    if let Err(err) = replay_file.save(&recording.path) {
+        eprintln!("Could not save the replay to {}: {}", recording.path.display(), err);
+    }
+}
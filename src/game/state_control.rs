@@ -0,0 +1,315 @@
+use super::*;
+
+const POST_GAME_SCREEN_COLOR: Color = Color::rgba(0.6, 0.6, 0.6, 0.8);
+
+const CONTACT_COLOR: Color = Color::RED;
+const CONTACT_RADIUS: f32 = 4.0;
+
+const STATS_FONT_SIZE: f32 = 30.0;
+
+pub struct StateControlPlugin;
+
+impl Plugin for StateControlPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(
+                SystemSet::on_enter(AppState::Editing)
+                .with_system(leave_game)
+            ).add_system_set(
+                SystemSet::on_exit(AppState::Editing)
+                .with_system(show_game)
+            ).add_system_set(
+                SystemSet::on_enter(AppState::PreGame)
+                .with_system(reset_pregame.label(NewGame))
+                .with_system(hide_cursor)
+            ).add_system_set(
+                SystemSet::on_update(AppState::PreGame)
+                .with_system(check_for_click.after(GameStep::Handles))
+            ).add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                // .with_system(check_out_of_bounds)
+                .with_system(check_rock_intersection.label(GameStep::Rocks).after(GameStep::Movement))
+            ).add_system_set(
+                SystemSet::on_enter(AppState::PostGame)
+                .with_system(post_game_screen)
+            ).add_system_set(
+                SystemSet::on_update(AppState::PostGame)
+                .with_system(check_for_restart)
+            )
+        ;
+    }
+}
+
+#[derive(Component)]
+pub struct EndScreenStuff;
+
+#[allow(clippy::too_many_arguments)]
+fn reset_pregame(
+    mut commands: Commands,
+    mut all_objects_transform_query: Query<&mut Transform, Without<CurvePath>>,
+    curve_path_entity_query: Query<Entity, With<CurvePath>>,
+    end_screen_entities_query: Query<Entity, With<EndScreenStuff>>,
+    last_point_entity_query: Query<Entity, With<LastPoint>>,
+    mut all_paths_query: Query<&mut Path, (Without<Transform>, Without<CurvePath>)>,
+    mut next_point_pos: ResMut<NextPointPos>,
+    mut current_curve: ResMut<CurrentCurve>,
+    mut t: ResMut<T>,
+    mut distance: ResMut<Distance>,
+    mut control_points: ResMut<ControlPoints>,
+    mut game_time: ResMut<GameTime>,
+    mut last_contact: ResMut<LastContact>,
+    mut game_rng: ResMut<GameRng>,
+    mut score: ResMut<Score>
+) {
+    if let Ok(curve_path_entity) = curve_path_entity_query.get_single() {
+        commands.entity(curve_path_entity).despawn();
+    }
+
+    if let Ok(last_point_entity) = last_point_entity_query.get_single() {
+        commands.entity(last_point_entity).despawn();
+    }
+
+    for end_screen_entity in end_screen_entities_query.iter() {
+        commands.entity(end_screen_entity).despawn();
+    }
+
+    for mut object_transform in all_objects_transform_query.iter_mut() {
+        object_transform.translation.x = 0.0;
+        object_transform.translation.y = 0.0;
+    }
+
+    for mut path in all_paths_query.iter_mut() {
+        *path = get_line_path(Vec2::ZERO, Vec2::ZERO);
+    }
+
+    t.0 = 0.0;
+    distance.0 = 0.0;
+    next_point_pos.0 = Vector3::zeros();
+    control_points.0 = Vector3::zeros();
+    control_points.1 = Vector3::zeros();
+    current_curve.0 = None;
+    game_time.0 = 0.0;
+    last_contact.0 = None;
+    game_rng.next_game();
+    score.0 = 0;
+}
+
+fn hide_cursor(
+    mut windows: ResMut<Windows>
+) {
+    windows.get_primary_mut().unwrap().set_cursor_visibility(false);
+}
+
+// Clears away whatever was left of the last game and hides the rest, the next
+// time the game starts from PreGame it resets everything anyway
+#[allow(clippy::complexity)]
+fn leave_game(
+    mut commands: Commands,
+    leftover_entities_query: Query<Entity, Or<(With<CurvePath>, With<LastPoint>, With<EndScreenStuff>)>>,
+    mut game_visibility_query: Query<&mut Visibility, With<GameEntity>>,
+    mut windows: ResMut<Windows>
+) {
+    for entity in leftover_entities_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    for mut visibility in game_visibility_query.iter_mut() {
+        visibility.is_visible = false;
+    }
+
+    windows.get_primary_mut().unwrap().set_cursor_visibility(true);
+}
+
+fn show_game(
+    mut game_visibility_query: Query<&mut Visibility, With<GameEntity>>
+) {
+    for mut visibility in game_visibility_query.iter_mut() {
+        visibility.is_visible = true;
+    }
+}
+
+fn check_for_click(
+    player_input: Res<PlayerInput>,
+    mut app_state: ResMut<State<AppState>>
+) {
+    if player_input.click {
+        let _ = app_state.set(AppState::InGame);
+    }
+}
+
+#[allow(dead_code)]
+fn check_out_of_bounds(
+    windows: Res<Windows>,
+    player_transform_query: Query<&Transform, With<Player>>,
+    mut app_state: ResMut<State<AppState>>
+) {
+    let window = windows.get_primary().unwrap();
+    let player_pos = player_transform_query.single().translation;
+
+    if 
+        player_pos.x > window.width()  /  2.0 ||
+        player_pos.x < window.width()  / -2.0 ||
+        player_pos.y > window.height() /  2.0 ||
+        player_pos.y < window.height() / -2.0
+    {
+        let _ = app_state.set(AppState::PostGame);
+    }
+}
+
+// Checks the whole stretch of curve the player moved along since the last frame, 
+// so a fast player can't skip over a thin rock
+#[allow(clippy::too_many_arguments)]
+fn check_rock_intersection(
+    rock_paths_query: Query<(&PolygonPoints, &PolygonBoundingBox)>,
+    rock_grid: Res<RockGrid>,
+    player_query: Query<(&Transform, Option<&Hitbox>), With<Player>>,
+    t: Res<T>,
+    current_curve: Res<CurrentCurve>,
+    mut last_t: Local<f32>,
+    mut last_contact: ResMut<LastContact>,
+    mut app_state: ResMut<State<AppState>>
+) {
+    let (player_transform, hitbox) = player_query.single();
+    let player_pos = Vec2::new(player_transform.translation.x, player_transform.translation.y);
+    let radius = hitbox.map_or(PLAYER_RADIUS, |hitbox| hitbox.radius);
+    let curve = match current_curve.0.as_ref() {
+        Some(curve) => curve,
+        None => return
+    };
+
+    // the curve resets when t wraps back around
+    let start_t = if *last_t <= t.0 { *last_t } else { 0.0 };
+    *last_t = t.0;
+
+    let swept_curve = curve.sub_curve(
+        curve.t_at_distance(start_t as f64 * curve.length()), 
+        curve.t_at_distance(t.0 as f64 * curve.length())
+    );
+    let swept_bbox = swept_curve.bounding_box().expanded(radius as f64);
+
+    let nearby_rocks = rock_grid.query(
+        swept_bbox.min.x as f32, 
+        swept_bbox.max.y as f32, 
+        swept_bbox.max.x as f32, 
+        swept_bbox.min.y as f32
+    );
+
+    for (rock_points, bbox) in rock_paths_query.iter_many(nearby_rocks) {
+        if 
+            (bbox.0 as f64) > swept_bbox.max.x ||
+            (bbox.1 as f64) < swept_bbox.min.y ||
+            (bbox.2 as f64) < swept_bbox.min.x ||
+            (bbox.3 as f64) > swept_bbox.max.y
+        { continue; }
+
+        // if the player passed right through the rock, the contact is where it first crossed the outline
+        let contact = circle_polygon_contact(&rock_points.0, player_pos, radius).or_else(|| {
+            let crossing_t = *swept_curve.intersect_polygon(&rock_points.0).first()?;
+            let crossing = swept_curve.get_point(crossing_t);
+
+            circle_polygon_contact(&rock_points.0, Vec2::new(crossing.x as f32, crossing.y as f32), radius)
+        });
+
+        if contact.is_some() {
+            last_contact.0 = contact;
+            let _ = app_state.set(AppState::PostGame);
+            return;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn post_game_screen(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    windows: Res<Windows>,
+    asset_server: Res<AssetServer>,
+    last_contact: Res<LastContact>,
+    level_progress: Res<LevelProgress>,
+    game_rng: Res<GameRng>,
+    score: Res<Score>,
+    camera_transform_query: Query<&Transform, With<Camera>>
+) {
+    let window = windows.get_primary().unwrap();
+    let camera_pos = camera_transform_query.single().translation;
+
+    commands.spawn((SpriteBundle {
+        sprite: Sprite {
+            color: POST_GAME_SCREEN_COLOR,
+            custom_size: Some(Vec2::new(window.width(), window.height())),
+            ..default()
+        },
+        transform: Transform
+            ::from_xyz(camera_pos.x, camera_pos.y, 5.0),
+        ..default()
+    }, EndScreenStuff));
+
+    if let Some(contact) = last_contact.0 {
+        spawn_point(
+            &mut commands, 
+            &mut meshes, 
+            &mut materials, 
+            CONTACT_COLOR, 
+            CONTACT_RADIUS, 
+            Transform::from_xyz(contact.point.x, contact.point.y, 5.1), 
+            EndScreenStuff
+        );
+    }
+
+    let font = asset_server.load("fonts/HankenGrotesk-VariableFont_wght.ttf");
+    let text_style = TextStyle { 
+        font: font.clone(), 
+        font_size: 100.0, 
+        color: Color::BLACK 
+    };
+
+    commands.spawn((TextBundle::from_section(
+        if level_progress.complete { "// Level Complete" } else { "// Game Over" }, 
+        text_style
+    )
+        .with_text_alignment(TextAlignment::TOP_CENTER)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(20.0),
+                left: Val::Px(40.0),
+                ..default()
+            },
+            justify_content: JustifyContent::Center,
+            ..default()
+        }), EndScreenStuff
+    ));
+
+    // cargo run -- --seed <seed> plays the same points and rocks again
+    commands.spawn((TextBundle::from_section(
+        format!("score: {}\nseed: {}", score.0, game_rng.seed()),
+        TextStyle { 
+            font, 
+            font_size: STATS_FONT_SIZE, 
+            color: Color::BLACK 
+        }
+    )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(140.0),
+                left: Val::Px(45.0),
+                ..default()
+            },
+            ..default()
+        }), EndScreenStuff
+    ));
+}
+
+fn check_for_restart(
+    buttons: Res<Input<MouseButton>>,
+    mut player_input: ResMut<PlayerInput>,
+    mut app_state: ResMut<State<AppState>>
+) {
+    if buttons.just_pressed(MouseButton::Left) {
+        // so the same click doesn't also start the next game
+        player_input.click = false;
+        let _ = app_state.set(AppState::PreGame);
+    }
+}
\ No newline at end of file
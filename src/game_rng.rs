@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+// Every part of the game draws from its own stream of the seed, so rocks and points
+// never end up built from the same numbers
+const GAMEPLAY_STREAM: u64 = 0;
+pub const ROCKS_STREAM: u64 = 1;
+
+// All the randomness in the game comes from here, so the same seed always gives the
+// same rocks and the same points
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    // a seed that was asked for gets played every game, otherwise each game gets a new one
+    fixed: bool,
+    rng: ChaCha8Rng
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            fixed: true,
+            rng: Self::stream(seed, GAMEPLAY_STREAM)
+        }
+    }
+
+    // For when nobody asked for a particular seed
+    pub fn unseeded() -> Self {
+        GameRng { fixed: false, ..Self::new(Self::random_seed()) }
+    }
+
+    fn stream(seed: u64, stream: u64) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(stream);
+
+        rng
+    }
+
+    fn random_seed() -> u64 {
+        thread_rng().gen()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // A separate generator for something that isn't drawn every game, like the rocks
+    pub fn fork(&self, stream: u64) -> ChaCha8Rng {
+        Self::stream(self.seed, stream)
+    }
+
+    // Goes back to the start of the sequence, so replaying a seed gives the same
+    // points no matter how many games came before
+    pub fn restart(&mut self) {
+        self.rng = Self::stream(self.seed, GAMEPLAY_STREAM);
+    }
+
+    // Every game starts with this, a seed that was asked for starts over and anything else gets a new seed
+    pub fn next_game(&mut self) {
+        if self.fixed {
+            self.restart();
+        } else {
+            *self = Self::unseeded();
+        }
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_repeats_the_same_numbers() {
+        let mut game_rng = GameRng::new(42);
+        let first: Vec<u64> = (0..8).map(|_| game_rng.next_u64()).collect();
+
+        game_rng.restart();
+        let second: Vec<u64> = (0..8).map(|_| game_rng.next_u64()).collect();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn next_game_only_repeats_fixed_seeds() {
+        let mut fixed = GameRng::new(42);
+        let mut unseeded = GameRng::unseeded();
+        let first_seed = unseeded.seed();
+
+        let first = fixed.next_u64();
+        fixed.next_game();
+        unseeded.next_game();
+
+        assert_eq!(fixed.seed(), 42);
+        assert_eq!(fixed.next_u64(), first);
+        assert_ne!(unseeded.seed(), first_seed);
+    }
+
+    #[test]
+    fn forks_dont_share_numbers() {
+        let mut game_rng = GameRng::new(42);
+        let mut rocks_rng = game_rng.fork(ROCKS_STREAM);
+
+        let gameplay: Vec<u64> = (0..64).map(|_| game_rng.next_u64()).collect();
+        let rocks: Vec<u64> = (0..64).map(|_| rocks_rng.next_u64()).collect();
+
+        assert!(rocks.iter().all(|n| !gameplay.contains(n)));
+        // but the same seed still gives the same rocks
+        assert_eq!(rocks[0], GameRng::new(42).fork(ROCKS_STREAM).next_u64());
+    }
+}
@@ -0,0 +1,488 @@
+use bevy::prelude::Vec2;
+
+// Polygons are lists of corners, the last corner connects back to the first.
+// Repeating the first corner at the end (like PolygonPoints does) is fine.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+    Degenerate
+}
+
+fn edges(polygon: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    (0..polygon.len()).map(move |i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+}
+
+// Positive if p is left of the line going from a to b, negative if it's to the right
+fn side_of_line(a: Vec2, b: Vec2, p: Vec2) -> f32 {
+    (b - a).perp_dot(p - a)
+}
+
+pub fn is_on_segment(a: Vec2, b: Vec2, p: Vec2) -> bool {
+    side_of_line(a, b, p) == 0.0 &&
+    a.x.min(b.x) <= p.x && p.x <= a.x.max(b.x) &&
+    a.y.min(b.y) <= p.y && p.y <= a.y.max(b.y)
+}
+
+// How many times the polygon winds counterclockwise around p
+pub fn winding_number(polygon: &[Vec2], p: Vec2) -> i32 {
+    let mut winding_number = 0;
+
+    for (a, b) in edges(polygon) {
+        if a.y <= p.y {
+            // an upward crossing with p on the left
+            if b.y > p.y && side_of_line(a, b, p) > 0.0 { winding_number += 1; }
+        } else {
+            // a downward crossing with p on the right
+            if b.y <= p.y && side_of_line(a, b, p) < 0.0 { winding_number -= 1; }
+        }
+    }
+
+    winding_number
+}
+
+// Points exactly on the outline count as inside
+pub fn point_in_polygon(polygon: &[Vec2], p: Vec2) -> bool {
+    edges(polygon).any(|(a, b)| is_on_segment(a, b, p)) || winding_number(polygon, p) != 0
+}
+
+// Positive for counterclockwise polygons, negative for clockwise ones
+pub fn signed_area(polygon: &[Vec2]) -> f32 {
+    edges(polygon).map(|(a, b)| a.perp_dot(b)).sum::<f32>() / 2.0
+}
+
+pub fn orientation(polygon: &[Vec2]) -> Orientation {
+    let area = signed_area(polygon);
+
+    if area > 0.0 {
+        Orientation::CounterClockwise
+    } else if area < 0.0 {
+        Orientation::Clockwise
+    } else {
+        Orientation::Degenerate
+    }
+}
+
+// The corners without any repeats, each with the index of its last copy in the polygon.
+// Repeated corners would make zero length edges.
+fn distinct_corners(polygon: &[Vec2]) -> Vec<(usize, Vec2)> {
+    let mut corners: Vec<(usize, Vec2)> = Vec::with_capacity(polygon.len());
+
+    for (i, p) in polygon.iter().enumerate() {
+        match corners.last_mut() {
+            // the edge leaving a repeated corner starts at its last copy
+            Some(last) if last.1 == *p => last.0 = i,
+            _ => corners.push((i, *p))
+        }
+    }
+
+    while corners.len() > 1 && corners.first().map(|corner| corner.1) == corners.last().map(|corner| corner.1) {
+        corners.pop();
+    }
+
+    corners
+}
+
+// Whether every corner turns the same way, polygons with no area are not convex
+pub fn is_convex(polygon: &[Vec2]) -> bool {
+    let corners: Vec<Vec2> = distinct_corners(polygon).into_iter().map(|(_, p)| p).collect();
+
+    if corners.len() < 3 { return false; }
+
+    let mut turn_direction = 0.0;
+    let mut total_angle = 0.0;
+
+    for i in 0..corners.len() {
+        let a = corners[i];
+        let b = corners[(i + 1) % corners.len()];
+        let c = corners[(i + 2) % corners.len()];
+
+        let turn = side_of_line(a, b, c);
+
+        if turn == 0.0 { continue; }
+
+        if turn_direction * turn < 0.0 { return false; }
+        turn_direction = turn;
+
+        total_angle += (b - a).angle_between(c - b);
+    }
+
+    // a star shape turns the same way at every corner, but goes around more than once
+    turn_direction != 0.0 && total_angle.abs() < 2.5 * std::f32::consts::PI
+}
+
+// Whether the segments from a to b and from c to d touch at all
+pub fn segments_intersect(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let (side_c, side_d) = (side_of_line(a, b, c), side_of_line(a, b, d));
+    let (side_a, side_b) = (side_of_line(c, d, a), side_of_line(c, d, b));
+
+    if (side_c * side_d < 0.0) && (side_a * side_b < 0.0) { return true; }
+
+    is_on_segment(a, b, c) || is_on_segment(a, b, d) || is_on_segment(c, d, a) || is_on_segment(c, d, b)
+}
+
+// Whether the segments from a to b and from c to d share an end and touch nowhere else
+fn only_share_end(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let (shared, other_ab, other_cd) = if a == c {
+        (a, b, d)
+    } else if a == d {
+        (a, b, c)
+    } else if b == c {
+        (b, a, d)
+    } else if b == d {
+        (b, a, c)
+    } else {
+        return false;
+    };
+
+    // segments heading the same way from the shared end overlap past it
+    side_of_line(shared, other_ab, other_cd) != 0.0 || (other_ab - shared).dot(other_cd - shared) < 0.0
+}
+
+// The indices of the first two edges that cross each other, where edge i goes from corner i to corner i + 1. 
+// Edges are allowed to share a corner, and repeated corners are skipped instead of making zero length edges.
+pub fn find_self_intersection(polygon: &[Vec2]) -> Option<(usize, usize)> {
+    let corners = distinct_corners(polygon);
+    let n = corners.len();
+
+    for i in 0..n {
+        // the last edge neighbours the first one
+        let last_edge = if i == 0 { n - 1 } else { n };
+
+        for j in (i + 2)..last_edge {
+            let (a, b) = (corners[i].1, corners[(i + 1) % n].1);
+            let (c, d) = (corners[j].1, corners[(j + 1) % n].1);
+
+            if segments_intersect(a, b, c, d) && !only_share_end(a, b, c, d) {
+                return Some((corners[i].0, corners[j].0));
+            }
+        }
+    }
+
+    None
+}
+
+pub fn closest_point_on_segment(a: Vec2, b: Vec2, p: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+
+    if length_squared == 0.0 { return a; }
+
+    a + ab * ((p - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+}
+
+// The closest point on the outline of the polygon to p
+pub fn closest_point_on_polygon(polygon: &[Vec2], p: Vec2) -> Option<Vec2> {
+    edges(polygon)
+        .map(|(a, b)| closest_point_on_segment(a, b, p))
+        .min_by(|a, b| a.distance_squared(p).partial_cmp(&b.distance_squared(p)).unwrap())
+}
+
+// Distance from p to the outline of the polygon, negative when p is inside
+pub fn signed_distance_to_polygon(polygon: &[Vec2], p: Vec2) -> f32 {
+    let distance = match closest_point_on_polygon(polygon, p) {
+        Some(closest_point) => closest_point.distance(p),
+        None => return f32::INFINITY
+    };
+
+    if point_in_polygon(polygon, p) { -distance } else { distance }
+}
+
+// Distance from p to the polygon, zero when p is inside
+pub fn distance_to_polygon(polygon: &[Vec2], p: Vec2) -> f32 {
+    signed_distance_to_polygon(polygon, p).max(0.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    // The closest point on the polygon's outline to the center of the circle
+    pub point: Vec2,
+    // The direction to push the circle to get it out of the polygon
+    pub normal: Vec2,
+    // How far the circle would have to be pushed
+    pub depth: f32
+}
+
+pub fn circle_polygon_contact(polygon: &[Vec2], center: Vec2, radius: f32) -> Option<Contact> {
+    let point = closest_point_on_polygon(polygon, center)?;
+    let inside = point_in_polygon(polygon, center);
+    let distance = point.distance(center);
+
+    if !inside && distance >= radius { return None; }
+
+    let (normal, depth) = if inside {
+        ((point - center).normalize_or_zero(), radius + distance)
+    } else {
+        ((center - point).normalize_or_zero(), radius - distance)
+    };
+
+    Some(Contact { point, normal, depth })
+}
+
+// Same as circle_polygon_contact, but for a circle that has to stay inside the polygon,
+// like the player inside a level's border
+pub fn circle_border_contact(polygon: &[Vec2], center: Vec2, radius: f32) -> Option<Contact> {
+    let point = closest_point_on_polygon(polygon, center)?;
+    let inside = point_in_polygon(polygon, center);
+    let distance = point.distance(center);
+
+    if inside && distance >= radius { return None; }
+
+    let (normal, depth) = if inside {
+        ((center - point).normalize_or_zero(), radius - distance)
+    } else {
+        ((point - center).normalize_or_zero(), radius + distance)
+    };
+
+    Some(Contact { point, normal, depth })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Vec<Vec2> {
+        vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0)
+        ]
+    }
+
+    #[test]
+    fn point_in_square() {
+        let square = square();
+
+        assert!(point_in_polygon(&square, Vec2::new(5.0, 5.0)));
+        assert!(!point_in_polygon(&square, Vec2::new(15.0, 5.0)));
+        assert!(!point_in_polygon(&square, Vec2::new(-5.0, 5.0)));
+        assert!(!point_in_polygon(&square, Vec2::new(5.0, -0.1)));
+    }
+
+    #[test]
+    fn point_at_origin() {
+        let square = square();
+        let centered: Vec<Vec2> = square.iter().map(|p| *p - Vec2::new(5.0, 5.0)).collect();
+
+        // on a corner
+        assert!(point_in_polygon(&square, Vec2::ZERO));
+        // strictly inside
+        assert!(point_in_polygon(&centered, Vec2::ZERO));
+    }
+
+    #[test]
+    fn point_on_vertical_edge() {
+        let square = square();
+
+        assert!(point_in_polygon(&square, Vec2::new(10.0, 5.0)));
+        assert!(point_in_polygon(&square, Vec2::new(0.0, 5.0)));
+        assert!(!point_in_polygon(&square, Vec2::new(10.001, 5.0)));
+    }
+
+    #[test]
+    fn point_level_with_vertex() {
+        let diamond = vec![
+            Vec2::new(0.0, -10.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(0.0, 10.0),
+            Vec2::new(-10.0, 0.0)
+        ];
+
+        assert!(point_in_polygon(&diamond, Vec2::new(5.0, 0.0)));
+        assert!(!point_in_polygon(&diamond, Vec2::new(15.0, 0.0)));
+        assert!(!point_in_polygon(&diamond, Vec2::new(-15.0, 0.0)));
+    }
+
+    #[test]
+    fn repeated_closing_point() {
+        let mut square = square();
+        square.push(square[0]);
+
+        assert!(point_in_polygon(&square, Vec2::new(5.0, 5.0)));
+        assert!(!point_in_polygon(&square, Vec2::new(15.0, 5.0)));
+        assert_eq!(signed_area(&square), 100.0);
+        assert!(is_convex(&square));
+    }
+
+    #[test]
+    fn concave_polygon() {
+        let u_shape = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(30.0, 0.0),
+            Vec2::new(30.0, 30.0),
+            Vec2::new(20.0, 30.0),
+            Vec2::new(20.0, 10.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(10.0, 30.0),
+            Vec2::new(0.0, 30.0)
+        ];
+
+        assert!(point_in_polygon(&u_shape, Vec2::new(5.0, 20.0)));
+        assert!(!point_in_polygon(&u_shape, Vec2::new(15.0, 20.0)));
+        assert!(!is_convex(&u_shape));
+    }
+
+    #[test]
+    fn clockwise_polygon() {
+        let mut square = square();
+        square.reverse();
+
+        assert!(point_in_polygon(&square, Vec2::new(5.0, 5.0)));
+        assert_eq!(winding_number(&square, Vec2::new(5.0, 5.0)), -1);
+        assert_eq!(orientation(&square), Orientation::Clockwise);
+        assert_eq!(signed_area(&square), -100.0);
+        assert!(is_convex(&square));
+    }
+
+    #[test]
+    fn degenerate_polygons() {
+        let empty: Vec<Vec2> = Vec::new();
+        let line = vec![Vec2::new(0.0, 0.0), Vec2::new(5.0, 5.0), Vec2::new(10.0, 10.0)];
+
+        assert!(!point_in_polygon(&empty, Vec2::ZERO));
+        assert_eq!(orientation(&empty), Orientation::Degenerate);
+        assert_eq!(distance_to_polygon(&empty, Vec2::ZERO), f32::INFINITY);
+
+        assert!(point_in_polygon(&line, Vec2::new(2.0, 2.0)));
+        assert!(!point_in_polygon(&line, Vec2::new(2.0, 3.0)));
+        assert_eq!(orientation(&line), Orientation::Degenerate);
+        assert!(!is_convex(&line));
+    }
+
+    #[test]
+    fn star_is_not_convex() {
+        let star: Vec<Vec2> = (0..5)
+            .map(|i| {
+                let angle = 4.0 * std::f32::consts::PI * i as f32 / 5.0;
+                Vec2::new(angle.cos(), angle.sin())
+            })
+            .collect();
+
+        assert!(!is_convex(&star));
+    }
+
+    #[test]
+    fn distances() {
+        let square = square();
+
+        assert_eq!(distance_to_polygon(&square, Vec2::new(13.0, 14.0)), 5.0);
+        assert_eq!(distance_to_polygon(&square, Vec2::new(5.0, 5.0)), 0.0);
+        assert_eq!(signed_distance_to_polygon(&square, Vec2::new(5.0, 8.0)), -2.0);
+        assert_eq!(closest_point_on_polygon(&square, Vec2::new(-3.0, 4.0)), Some(Vec2::new(0.0, 4.0)));
+    }
+
+    #[test]
+    fn circle_contacts() {
+        let square = square();
+
+        assert_eq!(circle_polygon_contact(&square, Vec2::new(20.0, 5.0), 5.0), None);
+        assert_eq!(circle_polygon_contact(&square, Vec2::new(15.0, 5.0), 5.0), None);
+        assert_eq!(
+            circle_polygon_contact(&square, Vec2::new(13.0, 5.0), 5.0),
+            Some(Contact { point: Vec2::new(10.0, 5.0), normal: Vec2::new(1.0, 0.0), depth: 2.0 })
+        );
+        assert_eq!(
+            circle_polygon_contact(&square, Vec2::new(8.0, 5.0), 1.0),
+            Some(Contact { point: Vec2::new(10.0, 5.0), normal: Vec2::new(1.0, 0.0), depth: 3.0 })
+        );
+        assert_eq!(circle_polygon_contact(&square, Vec2::new(10.0, 5.0), 1.0).map(|contact| contact.depth), Some(1.0));
+    }
+
+    #[test]
+    fn border_contacts() {
+        let border = vec![
+            Vec2::new(-500.0, -500.0),
+            Vec2::new(500.0, -500.0),
+            Vec2::new(500.0, 500.0),
+            Vec2::new(-500.0, 500.0)
+        ];
+
+        // well inside the border is where the player spends the whole game
+        assert_eq!(circle_border_contact(&border, Vec2::ZERO, 10.0), None);
+        assert_eq!(circle_border_contact(&border, Vec2::new(490.0, 0.0), 10.0), None);
+        assert_eq!(
+            circle_border_contact(&border, Vec2::new(495.0, 0.0), 10.0),
+            Some(Contact { point: Vec2::new(500.0, 0.0), normal: Vec2::new(-1.0, 0.0), depth: 5.0 })
+        );
+        assert_eq!(
+            circle_border_contact(&border, Vec2::new(520.0, 0.0), 10.0),
+            Some(Contact { point: Vec2::new(500.0, 0.0), normal: Vec2::new(-1.0, 0.0), depth: 30.0 })
+        );
+    }
+
+    #[test]
+    fn self_intersections() {
+        let square = square();
+        let bowtie = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(0.0, 10.0)
+        ];
+        let touching = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(5.0, 0.0),
+            Vec2::new(5.0, 10.0)
+        ];
+
+        assert_eq!(find_self_intersection(&square), None);
+        assert_eq!(find_self_intersection(&bowtie), Some((0, 2)));
+        assert!(find_self_intersection(&touching).is_some());
+        assert_eq!(find_self_intersection(&square[..2]), None);
+    }
+
+    #[test]
+    fn closed_and_repeated_corners_dont_cross() {
+        let mut closed = square();
+        closed.push(closed[0]);
+        let repeated = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0)
+        ];
+        // two triangles meeting at (10, 10)
+        let pinched = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(20.0, 10.0),
+            Vec2::new(20.0, 20.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0)
+        ];
+        // edge 3 doubles back along edge 1 from their shared corner
+        let folded = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(10.0, 5.0)
+        ];
+
+        assert_eq!(find_self_intersection(&closed), None);
+        assert_eq!(find_self_intersection(&repeated), None);
+        assert_eq!(find_self_intersection(&pinched), None);
+        assert!(find_self_intersection(&folded).is_some());
+    }
+
+    #[test]
+    fn crossings_past_repeated_corners() {
+        let bowtie = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(0.0, 10.0),
+            Vec2::new(0.0, 0.0)
+        ];
+
+        // the edges are numbered the same way as in the polygon that was passed in
+        assert_eq!(find_self_intersection(&bowtie), Some((1, 3)));
+    }
+}
@@ -0,0 +1,333 @@
+use bevy::prelude::{Resource, Vec2};
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::{fmt, fs, io::{self, Write}, path::Path};
+
+use crate::{geometry::{find_self_intersection, point_in_polygon}, rocks_plugin::RockStyle};
+
+// Bump this and add a step to migrate whenever the format changes
+pub const CURRENT_VERSION: u64 = 2;
+
+pub const LEVELS_DIR: &str = "src/bin/levels";
+
+#[derive(Serialize, Deserialize, Resource, Debug, Clone, PartialEq)]
+pub struct LevelFile {
+    pub version: u64,
+    pub border_points: Vec<[f32; 2]>,
+    pub anchors: Vec<[f32; 2]>,
+    pub end: [f32; 2],
+    pub rocks: Vec<RockInfo>
+}
+
+// An obstacle inside the play area
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockInfo {
+    pub id: u64,
+    pub style: RockStyle,
+    pub points: Vec<[f32; 2]>
+}
+
+impl RockInfo {
+    pub fn points(&self) -> Vec<Vec2> {
+        self.points.iter().map(|[x, y]| Vec2::new(*x, *y)).collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion(u64),
+    TooFewBorderPoints(usize),
+    // the indices of two border edges that cross
+    SelfIntersectingBorder(usize, usize),
+    EndOutsideBorder,
+    // the player always starts at the origin
+    StartOutsideBorder,
+    // the id of the rock and how many points it has
+    TooFewRockPoints(u64, usize),
+    DuplicateRockId(u64)
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Io(err) => write!(f, "Could not access the level file: {}", err),
+            LevelError::Parse(err) => write!(f, "Could not parse the level file: {}", err),
+            LevelError::UnsupportedVersion(version) => write!(
+                f,
+                "The level file is version {}, but the newest supported version is {}",
+                version,
+                CURRENT_VERSION
+            ),
+            LevelError::TooFewBorderPoints(n) => write!(f, "The border needs at least three points, but it has {}", n),
+            LevelError::SelfIntersectingBorder(i, j) => write!(f, "Border edges {} and {} cross each other", i, j),
+            LevelError::EndOutsideBorder => write!(f, "The end is outside of the border"),
+            LevelError::StartOutsideBorder => write!(f, "The player start (the origin) is outside of the border"),
+            LevelError::TooFewRockPoints(id, n) => write!(f, "Rock {} needs at least three points, but it has {}", id, n),
+            LevelError::DuplicateRockId(id) => write!(f, "There is more than one rock with the id {}", id)
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+    fn from(err: io::Error) -> Self {
+        LevelError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LevelError {
+    fn from(err: serde_json::Error) -> Self {
+        LevelError::Parse(err)
+    }
+}
+
+impl Default for LevelFile {
+    fn default() -> Self {
+        LevelFile {
+            version: CURRENT_VERSION,
+            border_points: Vec::new(),
+            anchors: Vec::new(),
+            end: [0.0, 200.0],
+            rocks: Vec::new()
+        }
+    }
+}
+
+// Files from before versioning have no version field and count as version 0
+fn migrate(mut value: Value) -> Result<Value, LevelError> {
+    let mut version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+
+    if version > CURRENT_VERSION { return Err(LevelError::UnsupportedVersion(version)); }
+
+    while version < CURRENT_VERSION {
+        match version {
+            // version 1 only added the version field
+            0 => {},
+            // version 2 added rocks
+            1 => {
+                if let Some(object) = value.as_object_mut() {
+                    object.insert("rocks".to_string(), Value::Array(Vec::new()));
+                }
+            },
+            _ => unreachable!()
+        }
+
+        version += 1;
+    }
+
+    if let Some(object) = value.as_object_mut() {
+        object.insert("version".to_string(), Value::from(version));
+    }
+
+    Ok(value)
+}
+
+impl LevelFile {
+    // Parses a level from any version of the format, upgrading it to the current one
+    pub fn from_json(json: &str) -> Result<Self, LevelError> {
+        let value = migrate(serde_json::from_str(json)?)?;
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, LevelError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LevelError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    // Writes to a temporary file and renames it over the level, so a crash
+    // partway through never leaves a half written level behind
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LevelError> {
+        let path = path.as_ref();
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(self.to_json()?.as_bytes())?;
+        file.sync_all()?;
+
+        Ok(fs::rename(&temp_path, path)?)
+    }
+
+    // Checks that the level can actually be played
+    pub fn validate(&self) -> Result<(), LevelError> {
+        let border = self.border();
+
+        if border.len() < 3 { return Err(LevelError::TooFewBorderPoints(border.len())); }
+
+        if let Some((i, j)) = find_self_intersection(&border) {
+            return Err(LevelError::SelfIntersectingBorder(i, j));
+        }
+
+        if !point_in_polygon(&border, self.end()) { return Err(LevelError::EndOutsideBorder); }
+
+        if !point_in_polygon(&border, Vec2::ZERO) { return Err(LevelError::StartOutsideBorder); }
+
+        for (i, rock) in self.rocks.iter().enumerate() {
+            if rock.points.len() < 3 { return Err(LevelError::TooFewRockPoints(rock.id, rock.points.len())); }
+
+            if self.rocks[..i].iter().any(|other| other.id == rock.id) {
+                return Err(LevelError::DuplicateRockId(rock.id));
+            }
+        }
+
+        Ok(())
+    }
+
+    // An id that no rock in the level has yet
+    pub fn next_rock_id(&self) -> u64 {
+        self.rocks.iter().map(|rock| rock.id + 1).max().unwrap_or(0)
+    }
+
+    pub fn rock(&self, id: u64) -> Option<&RockInfo> {
+        self.rocks.iter().find(|rock| rock.id == id)
+    }
+
+    pub fn rock_mut(&mut self, id: u64) -> Option<&mut RockInfo> {
+        self.rocks.iter_mut().find(|rock| rock.id == id)
+    }
+
+    pub fn border(&self) -> Vec<Vec2> {
+        self.border_points.iter().map(|[x, y]| Vec2::new(*x, *y)).collect()
+    }
+
+    pub fn anchors(&self) -> Vec<Vec2> {
+        self.anchors.iter().map(|[x, y]| Vec2::new(*x, *y)).collect()
+    }
+
+    pub fn end(&self) -> Vec2 {
+        Vec2::new(self.end[0], self.end[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square_level() -> LevelFile {
+        LevelFile {
+            border_points: vec![[-500.0, -500.0], [500.0, -500.0], [500.0, 500.0], [-500.0, 500.0]],
+            anchors: vec![[100.0, 0.0]],
+            end: [0.0, 200.0],
+            ..LevelFile::default()
+        }
+    }
+
+    fn rock(id: u64) -> RockInfo {
+        RockInfo {
+            id,
+            style: RockStyle::default(),
+            points: vec![[200.0, 200.0], [300.0, 200.0], [300.0, 300.0]]
+        }
+    }
+
+    #[test]
+    fn migrate_version_0() {
+        let json = r#"{ "border_points": [[0.0, 0.0]], "anchors": [], "end": [1.0, 2.0] }"#;
+        let level = LevelFile::from_json(json).unwrap();
+
+        assert_eq!(level.version, CURRENT_VERSION);
+        assert_eq!(level.border_points, vec![[0.0, 0.0]]);
+        assert_eq!(level.end, [1.0, 2.0]);
+        assert!(level.rocks.is_empty());
+    }
+
+    #[test]
+    fn migrate_version_1() {
+        let json = r#"{ "version": 1, "border_points": [], "anchors": [[3.0, 4.0]], "end": [0.0, 0.0] }"#;
+        let level = LevelFile::from_json(json).unwrap();
+
+        assert_eq!(level.version, CURRENT_VERSION);
+        assert_eq!(level.anchors, vec![[3.0, 4.0]]);
+        assert!(level.rocks.is_empty());
+    }
+
+    #[test]
+    fn current_version_round_trips() {
+        let mut level = square_level();
+        level.rocks.push(rock(3));
+
+        assert_eq!(LevelFile::from_json(&level.to_json().unwrap()).unwrap(), level);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let json = format!(r#"{{ "version": {}, "border_points": [], "anchors": [], "end": [0.0, 0.0], "rocks": [] }}"#, CURRENT_VERSION + 1);
+
+        assert!(matches!(
+            LevelFile::from_json(&json),
+            Err(LevelError::UnsupportedVersion(version)) if version == CURRENT_VERSION + 1
+        ));
+        assert!(matches!(LevelFile::from_json("not a level"), Err(LevelError::Parse(_))));
+    }
+
+    #[test]
+    fn valid_levels() {
+        let mut closed = square_level();
+        closed.border_points.push(closed.border_points[0]);
+        let mut repeated = square_level();
+        repeated.border_points.insert(1, repeated.border_points[1]);
+
+        assert!(square_level().validate().is_ok());
+        assert!(closed.validate().is_ok());
+        assert!(repeated.validate().is_ok());
+    }
+
+    #[test]
+    fn too_few_border_points() {
+        let mut level = square_level();
+        level.border_points.truncate(2);
+
+        assert!(matches!(level.validate(), Err(LevelError::TooFewBorderPoints(2))));
+    }
+
+    #[test]
+    fn self_intersecting_border() {
+        let mut level = square_level();
+        level.border_points.swap(1, 2);
+
+        assert!(matches!(level.validate(), Err(LevelError::SelfIntersectingBorder(0, 2))));
+    }
+
+    #[test]
+    fn end_outside_border() {
+        let mut level = square_level();
+        level.end = [600.0, 0.0];
+
+        assert!(matches!(level.validate(), Err(LevelError::EndOutsideBorder)));
+    }
+
+    #[test]
+    fn start_outside_border() {
+        let mut level = square_level();
+        level.border_points = vec![[100.0, 100.0], [500.0, 100.0], [500.0, 500.0], [100.0, 500.0]];
+        level.end = [200.0, 200.0];
+
+        assert!(matches!(level.validate(), Err(LevelError::StartOutsideBorder)));
+    }
+
+    #[test]
+    fn too_few_rock_points() {
+        let mut level = square_level();
+        let mut flat_rock = rock(5);
+        flat_rock.points.pop();
+        level.rocks.push(flat_rock);
+
+        assert!(matches!(level.validate(), Err(LevelError::TooFewRockPoints(5, 2))));
+    }
+
+    #[test]
+    fn duplicate_rock_ids() {
+        let mut level = square_level();
+        level.rocks = vec![rock(0), rock(1), rock(0)];
+
+        assert!(matches!(level.validate(), Err(LevelError::DuplicateRockId(0))));
+        assert_eq!(level.next_rock_id(), 2);
+    }
+}
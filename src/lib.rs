@@ -0,0 +1,9 @@
+// Everything shared between the game and the level editor
+
+pub mod camera_tools;
+pub mod game;
+pub mod game_rng;
+pub mod geometry;
+pub mod level_format;
+pub mod rocks_plugin;
+pub mod spline;
@@ -1,77 +1,101 @@
-pub use bevy::{prelude::*, sprite::MaterialMesh2dBundle, ecs::system::EntityCommands};
-pub use bevy_prototype_lyon::prelude::*;
-pub use nalgebra::Vector3;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_ier_curves::{game::*, rocks_plugin::test_rocks};
 
-mod spline;
-pub use spline::*;
+const USAGE: &str = "usage: bevy-ier_curves [level name] [--seed <seed>] [--record <replay file>]\n       bevy-ier_curves --daily [--record <replay file>]\n       bevy-ier_curves --replay <replay file>";
 
-mod handle_plugin;
-use handle_plugin::*;
-
-mod curve_movement;
-use curve_movement::*;
-
-mod state_control;
-use state_control::*;
-
-mod camera_rubber_banding;
-use camera_rubber_banding::*;
-
-mod lifetime_plugin;
-use lifetime_plugin::*;
-
-mod rocks_plugin;
-use rocks_plugin::*;
-
-mod point_tools;
-use point_tools::*;
-
-const BACKGROUND_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
-
-pub const POINT_COLOR: Color = Color::rgb(0.5, 0.3, 0.3);
-pub const POINT_RADIUS: f32 = 7.0;
-
-const PLAYER_COLOR: Color = Color::RED;
-const PLAYER_RADIUS: f32 = 10.0;
-
-#[derive(Resource)]
-struct T(f32);
-
-#[derive(Resource)]
-struct StartGameTime(f32);
-
-#[derive(Resource)]
-struct NextPointPos(Vector3<f64>);
-
-#[derive(Resource)]
-struct ControlPoints(Vector3<f64>, Vector3<f64>);
+fn main() {
+    // cargo run -- <level name> plays a level made in the level editor,
+    // --seed <seed> replays a game from the seed on its game over screen,
+    // --daily plays the same course as everyone else today,
+    // --record <replay file> saves each game as it ends so --replay <replay file> can play it back
+    let mut level_name = None;
+    let mut seed = None;
+    let mut daily = false;
+    let mut record_path = None;
+    let mut replay_path = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => match args.next().and_then(|seed| seed.parse::<u64>().ok()) {
+                Some(value) => seed = Some(value),
+                None => exit_with_usage("--seed needs a number")
+            },
+            "--daily" => daily = true,
+            "--record" => match args.next() {
+                Some(path) => record_path = Some(path),
+                None => exit_with_usage("--record needs a file to save the replay to")
+            },
+            "--replay" => match args.next() {
+                Some(path) => replay_path = Some(path),
+                None => exit_with_usage("--replay needs a replay file")
+            },
+            _ if level_name.is_none() && !arg.starts_with('-') => level_name = Some(arg),
+            _ => exit_with_usage(&format!("unexpected argument {}", arg))
+        }
+    }
 
-#[derive(Resource)]
-struct CurrentCurve(Option<Curve>);
+    // a replay brings its own seed and level
+    let replay = replay_path.map(|path| {
+        if daily || level_name.is_some() || seed.is_some() || record_path.is_some() {
+            exit_with_usage("--replay plays back exactly what was recorded, it can't be used with anything else");
+        }
+
+        let replay_file = ReplayFile::load(&path).unwrap_or_else(|err| {
+            eprintln!("Could not load the replay {}: {}", path, err);
+            std::process::exit(1);
+        });
+        seed = Some(replay_file.seed);
+        level_name = replay_file.level.clone();
+
+        Replay::from(replay_file)
+    });
+
+    let daily_challenge = if daily {
+        if level_name.is_some() || seed.is_some() {
+            exit_with_usage("--daily picks its own course, it can't be used with a level or a seed");
+        }
+
+        let daily_challenge = DailyChallenge::today();
+        seed = Some(daily_challenge.seed());
+        Some(daily_challenge)
+    } else {
+        None
+    };
+
+    let recording = record_path.map(|path| Recording::new(path, level_name.clone()));
+
+    let level = level_name.map(|name| Level::load(&name).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }));
+
+    let mut app = App::new();
+
+    match level {
+        Some(level) => app.insert_resource(level),
+        None => app.add_system_set(
+            SystemSet::on_enter(AppState::PreGame)
+            .with_system(test_rocks.after(NewGame))
+        )
+    };
+
+    if let Some(daily_challenge) = daily_challenge {
+        app.insert_resource(daily_challenge);
+    }
 
-#[derive(Resource)]
-struct CursorPos(Vec2);
+    if let Some(replay) = replay {
+        app.insert_resource(replay);
+    }
 
-#[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-enum AppState {
-    PreGame,
-    InGame,
-    PostGame
-}
+    if let Some(recording) = recording {
+        app.insert_resource(recording);
+    }
 
-fn main() {
-    App::new()
-        .insert_resource(T(0.0))
-        .insert_resource(StartGameTime(0.0))
-        .insert_resource(NextPointPos(Vector3::new(0.0, 0.0, 0.0)))
-        .insert_resource(ControlPoints(Vector3::zeros(), Vector3::zeros()))
-        .insert_resource(CurrentCurve(None))
-        .insert_resource(CursorPos(Vec2::ZERO))
-        
+    app
         .insert_resource(Msaa { samples: 4 })
         .insert_resource(ClearColor(BACKGROUND_COLOR))
-        .add_state(AppState::PreGame)
 
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
@@ -81,80 +105,20 @@ fn main() {
             ..Default::default()
         }))
         .add_plugin(ShapePlugin)
+        .add_plugin(GamePlugin { initial_state: AppState::PreGame, seed })
 
-        .add_plugin(HandlePlugin)
-        .add_plugin(CurveMovementPlugin)
-        .add_plugin(StateControlPlugin)
-        .add_plugin(CameraRubberBandingPlugin)
-        .add_plugin(LifetimePlugin)
-        .add_plugin(RocksPlugin)
-        
         .add_startup_system(setup)
         .add_system(bevy::window::close_on_esc)
-        .add_system(set_cursor_pos)
         .run();
 }
 
-#[derive(Component)]
-struct NextPoint;
-
-#[derive(Component)]
-struct Player;
-
 fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut windows: ResMut<Windows>
+    mut commands: Commands
 ) {
-    let window = windows.get_primary_mut().unwrap();
-
-    window.set_cursor_visibility(false);
-
     commands.spawn(Camera2dBundle::default());
-
-    // commands.spawn(SpriteBundle {
-    //     sprite: Sprite {
-    //         color: Color::rgba(1.0, 1.0, 1.0, 0.1),
-    //         custom_size: Some(Vec2::new(window.width(), window.height())),
-    //         ..default()
-    //     },
-    //     transform: Transform
-    //         ::from_xyz(0.0, 0.0, 0.0),
-    //     ..default()
-    // });
-
-    spawn_point(
-        &mut commands, 
-        &mut meshes, 
-        &mut materials, 
-        POINT_COLOR, 
-        POINT_RADIUS, 
-        Transform::from_xyz(0.0, 0.0, 2.9), 
-        NextPoint
-    );
-
-    spawn_point(
-        &mut commands, 
-        &mut meshes, 
-        &mut materials, 
-        PLAYER_COLOR, 
-        PLAYER_RADIUS, 
-        Transform::from_xyz(0.0, 0.0, 3.0), 
-        Player
-    );
 }
 
-fn set_cursor_pos(
-    mut cursor_pos: ResMut<CursorPos>,
-    camera_transform_query: Query<&Transform, With<Camera>>,
-    windows: Res<Windows>
-) {
-    let window = windows.get_primary().unwrap();
-    let camera_pos = camera_transform_query.single().translation;
-
-    if let Some(cursor_position) = window.cursor_position() {
-        cursor_pos.0.x = cursor_position.x - window.width() / 2.0 + camera_pos.x;
-        cursor_pos.0.y = cursor_position.y - window.height() / 2.0 + camera_pos.y;
-    }
-}
\ No newline at end of file
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    std::process::exit(2);
+}
@@ -1,29 +1,146 @@
 use bevy_prototype_lyon::{prelude::*, entity::ShapeBundle};
 use bevy::prelude::*;
 
-use std::{f32::{INFINITY, NEG_INFINITY, consts::PI}, ops::Range};
-use rand::prelude::{thread_rng, Rng};
+use std::{f32::{INFINITY, NEG_INFINITY, consts::PI}, ops::Range, collections::{HashMap, HashSet}};
+use rand::prelude::Rng;
+
+use crate::game_rng::{GameRng, ROCKS_STREAM};
+use serde::{Serialize, Deserialize};
 
-const ROCK_FILL_COLOR: Color = Color::rgba(0.0, 0.5, 0.4, 0.2);
-const ROCK_OUTLINE_COLOR: Color = Color::rgb(0.0, 0.5, 0.4);
 const ROCK_OUTLINE_WIDTH: f32 = 2.0;
 
+const ROCK_GRID_CELL_SIZE: f32 = 250.0;
+// How far past the edge of the window rocks start being drawn
+const ROCK_VISIBILITY_MARGIN: f32 = 100.0;
+
 pub struct RocksPlugin;
 
 impl Plugin for RocksPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_startup_system(test_rocks)
+            .init_resource::<RockGrid>()
+            .add_system(index_rocks)
+            .add_system(update_rock_visibility.after(index_rocks))
         ;
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RockStyle {
+    #[default]
+    Teal,
+    Slate,
+    Clay
+}
+
+impl RockStyle {
+    pub const ALL: [RockStyle; 3] = [RockStyle::Teal, RockStyle::Slate, RockStyle::Clay];
+
+    pub fn outline_color(&self) -> Color {
+        match self {
+            RockStyle::Teal => Color::rgb(0.0, 0.5, 0.4),
+            RockStyle::Slate => Color::rgb(0.3, 0.35, 0.45),
+            RockStyle::Clay => Color::rgb(0.7, 0.35, 0.2)
+        }
+    }
+
+    pub fn fill_color(&self) -> Color {
+        *self.outline_color().set_a(0.2)
+    }
+
+    // The style after this one, wrapping back around to the first
+    pub fn next(&self) -> RockStyle {
+        let index = Self::ALL.iter().position(|style| style == self).unwrap();
+
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
 #[derive(Component)]
 pub struct PolygonPoints(pub Vec<Vec2>);
 
 #[derive(Component, Debug)]
 pub struct PolygonBoundingBox(pub f32, pub f32, pub f32, pub f32);
 
+// The lowest and highest (x, y) cells something covers
+type CellRange = ((i32, i32), (i32, i32));
+
+// A uniform grid of every rock's bounding box, so finding the rocks 
+// in an area only has to look at the cells that area covers
+#[derive(Resource, Default)]
+pub struct RockGrid {
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+    // the range of cells each rock is in
+    rock_cells: HashMap<Entity, CellRange>
+}
+
+impl RockGrid {
+    fn cell_range(left: f32, top: f32, right: f32, bottom: f32) -> CellRange {
+        (
+            ((left / ROCK_GRID_CELL_SIZE).floor() as i32, (bottom / ROCK_GRID_CELL_SIZE).floor() as i32),
+            ((right / ROCK_GRID_CELL_SIZE).floor() as i32, (top / ROCK_GRID_CELL_SIZE).floor() as i32)
+        )
+    }
+
+    pub fn insert(&mut self, entity: Entity, bbox: &PolygonBoundingBox) {
+        self.remove(entity);
+
+        let range = Self::cell_range(bbox.0, bbox.1, bbox.2, bbox.3);
+
+        for x in (range.0).0..=(range.1).0 {
+            for y in (range.0).1..=(range.1).1 {
+                self.cells.entry((x, y)).or_default().push(entity);
+            }
+        }
+
+        self.rock_cells.insert(entity, range);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        let range = match self.rock_cells.remove(&entity) {
+            Some(range) => range,
+            None => return
+        };
+
+        for x in (range.0).0..=(range.1).0 {
+            for y in (range.0).1..=(range.1).1 {
+                if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                    cell.retain(|other| *other != entity);
+
+                    if cell.is_empty() { self.cells.remove(&(x, y)); }
+                }
+            }
+        }
+    }
+
+    // Every rock whose bounding box might overlap the area, each one only once
+    pub fn query(&self, left: f32, top: f32, right: f32, bottom: f32) -> Vec<Entity> {
+        let range = Self::cell_range(left, top, right, bottom);
+        let mut entities = Vec::new();
+
+        for x in (range.0).0..=(range.1).0 {
+            for y in (range.0).1..=(range.1).1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    entities.extend(cell.iter().copied());
+                }
+            }
+        }
+
+        entities.sort_unstable();
+        entities.dedup();
+
+        entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.rock_cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rock_cells.is_empty()
+    }
+}
+
 #[derive(Bundle)]
 pub struct RockBundle {
     #[bundle]
@@ -33,7 +150,11 @@ pub struct RockBundle {
 }
 
 impl RockBundle {
-    pub fn new(mut points: Vec<Vec2>) -> Self {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        Self::with_style(points, RockStyle::default())
+    }
+
+    pub fn with_style(mut points: Vec<Vec2>, style: RockStyle) -> Self {
         let mut bbox = PolygonBoundingBox(
             INFINITY,     // left
             NEG_INFINITY, // top
@@ -62,8 +183,8 @@ impl RockBundle {
             shape: GeometryBuilder::build_as(
                 &shape, 
                 DrawMode::Outlined { 
-                    fill_mode: FillMode::color(ROCK_FILL_COLOR), 
-                    outline_mode: StrokeMode::new(ROCK_OUTLINE_COLOR, ROCK_OUTLINE_WIDTH) 
+                    fill_mode: FillMode::color(style.fill_color()), 
+                    outline_mode: StrokeMode::new(style.outline_color(), ROCK_OUTLINE_WIDTH) 
                 }, 
                 Transform::from_xyz(0.0, 0.0, 8.0)
             ),
@@ -73,13 +194,13 @@ impl RockBundle {
     }
 
     pub fn rand(
+        rng: &mut impl Rng,
         sides: usize,
         size: f32,
         center_range_x: Range<f32>, 
         center_range_y: Range<f32>, 
         corner_deviation_range: Range<f32>
     ) -> Self {
-        let mut rng = thread_rng();
         let angle_offset = rng.gen_range(0.0..(2.0 * PI));
         let center = Vec2::new(
             rng.gen_range(center_range_x.clone()),
@@ -104,48 +225,175 @@ impl RockBundle {
     }
 }
 
-fn test_rocks(
-    mut commands: Commands
+// New rocks start hidden until update_rock_visibility finds them on screen
+fn index_rocks(
+    mut rock_grid: ResMut<RockGrid>,
+    mut new_rocks_query: Query<(Entity, &PolygonBoundingBox, &mut Visibility), Changed<PolygonBoundingBox>>,
+    removed_rocks: RemovedComponents<PolygonBoundingBox>
 ) {
+    for entity in removed_rocks.iter() {
+        rock_grid.remove(entity);
+    }
+
+    for (entity, bbox, mut visibility) in new_rocks_query.iter_mut() {
+        rock_grid.insert(entity, bbox);
+        visibility.is_visible = false;
+    }
+}
+
+fn update_rock_visibility(
+    rock_grid: Res<RockGrid>,
+    windows: Res<Windows>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    mut rock_visibility_query: Query<&mut Visibility, With<PolygonPoints>>,
+    mut visible_rocks: Local<HashSet<Entity>>
+) {
+    let window = windows.get_primary().unwrap();
+    let (camera_transform, projection) = camera_query.single();
+    let camera_pos = camera_transform.translation;
+    let half_width = window.width() / 2.0 * projection.scale + ROCK_VISIBILITY_MARGIN;
+    let half_height = window.height() / 2.0 * projection.scale + ROCK_VISIBILITY_MARGIN;
+
+    let now_visible: HashSet<Entity> = rock_grid.query(
+        camera_pos.x - half_width,
+        camera_pos.y + half_height,
+        camera_pos.x + half_width,
+        camera_pos.y - half_height
+    ).into_iter().collect();
+
+    for entity in visible_rocks.difference(&now_visible) {
+        if let Ok(mut visibility) = rock_visibility_query.get_mut(*entity) {
+            visibility.is_visible = false;
+        }
+    }
+
+    for entity in now_visible.iter() {
+        if let Ok(mut visibility) = rock_visibility_query.get_mut(*entity) {
+            visibility.is_visible = true;
+        }
+    }
+
+    *visible_rocks = now_visible;
+}
+
+// Rocks for playing without a level, made again from each game's seed
+#[derive(Component)]
+pub struct TestRock;
+
+pub fn test_rocks(
+    mut commands: Commands,
+    game_rng: Res<GameRng>,
+    test_rock_query: Query<Entity, With<TestRock>>
+) {
+    for entity in test_rock_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let mut rng = game_rng.fork(ROCKS_STREAM);
+
     for i in 0..100 {
         let x = 200.0 * (i / 2) as f32;
         let y = 500.0 * (i % 2) as f32 - 250.0;
 
-        commands.spawn(RockBundle::rand(
+        commands.spawn((RockBundle::rand(
+            &mut rng,
             4,
             100.0,
             x..(x + 0.01),
             y..(y + 0.01),
             0.0..40.0
-        ));
+        ), TestRock));
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A square rock with its bottom left corner at (x, y)
+    fn rock_bbox(x: f32, y: f32, size: f32) -> PolygonBoundingBox {
+        PolygonBoundingBox(x, y + size, x + size, y)
+    }
+
+    fn entity(index: u32) -> Entity {
+        Entity::from_raw(index)
+    }
+
+    #[test]
+    fn insert_and_query() {
+        let mut rock_grid = RockGrid::default();
+        rock_grid.insert(entity(0), &rock_bbox(10.0, 10.0, 50.0));
+        rock_grid.insert(entity(1), &rock_bbox(1000.0, 1000.0, 50.0));
+
+        assert_eq!(rock_grid.len(), 2);
+        assert_eq!(rock_grid.query(0.0, 100.0, 100.0, 0.0), vec![entity(0)]);
+        assert_eq!(rock_grid.query(990.0, 1100.0, 1100.0, 990.0), vec![entity(1)]);
+        assert!(rock_grid.query(500.0, 600.0, 600.0, 500.0).is_empty());
+    }
+
+    #[test]
+    fn remove() {
+        let mut rock_grid = RockGrid::default();
+        rock_grid.insert(entity(0), &rock_bbox(10.0, 10.0, 50.0));
+        rock_grid.insert(entity(1), &rock_bbox(20.0, 20.0, 50.0));
 
-#[allow(dead_code)]
-pub fn is_intersecting(polygon_points: &Vec<Vec2>, player_pos: &Vec2) -> bool {
-    if player_pos.x == 0.0 && player_pos.y == 0.0 { return false };
+        rock_grid.remove(entity(0));
+        // removing something that isn't there does nothing
+        rock_grid.remove(entity(0));
 
-    let mut num_oultine_intersections = 0;
-    let slope = player_pos.y / (player_pos.x + 0.0001);
+        assert_eq!(rock_grid.len(), 1);
+        assert_eq!(rock_grid.query(0.0, 100.0, 100.0, 0.0), vec![entity(1)]);
 
-    for i in 0..polygon_points.len() {
-        let p = [polygon_points[i], polygon_points[(i + 1) % polygon_points.len()]];
+        rock_grid.remove(entity(1));
 
-        // If both points are on the same side of the line formed by the player and (0, 0)
-        if (p[0].x * slope > p[0].y) == (p[1].x * slope > p[1].y) { continue; }
+        assert!(rock_grid.is_empty());
+        assert!(rock_grid.cells.is_empty());
+    }
+
+    #[test]
+    fn reinserting_moves_a_rock() {
+        let mut rock_grid = RockGrid::default();
+        rock_grid.insert(entity(0), &rock_bbox(10.0, 10.0, 50.0));
+        rock_grid.insert(entity(0), &rock_bbox(1000.0, 1000.0, 50.0));
+
+        assert_eq!(rock_grid.len(), 1);
+        assert!(rock_grid.query(0.0, 100.0, 100.0, 0.0).is_empty());
+        assert_eq!(rock_grid.query(990.0, 1100.0, 1100.0, 990.0), vec![entity(0)]);
+    }
+
+    #[test]
+    fn query_across_cell_boundaries() {
+        let mut rock_grid = RockGrid::default();
+        // one rock either side of the line between the first two columns of cells
+        rock_grid.insert(entity(0), &rock_bbox(ROCK_GRID_CELL_SIZE - 20.0, 10.0, 10.0));
+        rock_grid.insert(entity(1), &rock_bbox(ROCK_GRID_CELL_SIZE + 10.0, 10.0, 10.0));
 
-        let poly_slope = (p[1].y - p[0].y) / (p[1].x - p[0].x + 0.0001);
-        let poly_y_int = p[0].y - poly_slope * p[0].x;
-        let intersection_x = poly_y_int / (slope - poly_slope);
+        let size = ROCK_GRID_CELL_SIZE;
 
-        // Or if the intersection point of the lines is outside of the segment
-        if 
-            (intersection_x < 0.0) != (player_pos.x < 0.0) ||
-            player_pos.x.abs() < intersection_x.abs()
-        { continue; }
+        assert_eq!(rock_grid.query(size - 5.0, 30.0, size + 5.0, 0.0), vec![entity(0), entity(1)]);
+        assert_eq!(rock_grid.query(0.0, 30.0, size - 5.0, 0.0), vec![entity(0)]);
+        assert_eq!(rock_grid.query(size + 5.0, 30.0, 2.0 * size - 5.0, 0.0), vec![entity(1)]);
+    }
+
+    #[test]
+    fn rocks_in_several_cells_come_back_once() {
+        let mut rock_grid = RockGrid::default();
+        // covers a 3 by 3 block of cells
+        rock_grid.insert(entity(0), &rock_bbox(10.0, 10.0, 2.5 * ROCK_GRID_CELL_SIZE));
 
-        num_oultine_intersections += 1;
+        assert_eq!(rock_grid.cells.len(), 9);
+        assert_eq!(rock_grid.query(0.0, 3.0 * ROCK_GRID_CELL_SIZE, 3.0 * ROCK_GRID_CELL_SIZE, 0.0), vec![entity(0)]);
     }
 
-    num_oultine_intersections % 2 == 1
-}
\ No newline at end of file
+    #[test]
+    fn negative_coordinates() {
+        let mut rock_grid = RockGrid::default();
+        // just below and left of the origin, which floors into cell (-1, -1) instead of (0, 0)
+        rock_grid.insert(entity(0), &rock_bbox(-30.0, -30.0, 20.0));
+        rock_grid.insert(entity(1), &rock_bbox(10.0, 10.0, 20.0));
+
+        assert_eq!(RockGrid::cell_range(-30.0, -10.0, -10.0, -30.0), ((-1, -1), (-1, -1)));
+        assert_eq!(rock_grid.query(-50.0, -5.0, -5.0, -50.0), vec![entity(0)]);
+        assert_eq!(rock_grid.query(-50.0, 50.0, 50.0, -50.0), vec![entity(0), entity(1)]);
+        assert_eq!(rock_grid.query(5.0, 50.0, 50.0, 5.0), vec![entity(1)]);
+    }
+}
@@ -2,6 +2,18 @@ use nalgebra::{Vector4, Vector3, Matrix4x3, Matrix4};
 use bevy_prototype_lyon::prelude::{Path, PathBuilder};
 use bevy::prelude::Vec2;
 
+// Number of segments used to approximate the arc length of a curve
+const ARC_LENGTH_SAMPLES: usize = 64;
+
+// Number of segments sampled to find starting guesses for the closest point on a curve
+const PROJECTION_SAMPLES: usize = 32;
+const PROJECTION_ITERATIONS: usize = 8;
+
+// How small both curves have to get before they count as intersecting
+const INTERSECTION_TOLERANCE: f64 = 1e-4;
+const INTERSECTION_MAX_DEPTH: usize = 40;
+const ROOT_BISECTION_ITERATIONS: usize = 60;
+
 pub fn get_line_path(p1: Vec2, p2: Vec2) -> Path {
     let mut path_builder = PathBuilder::new();
     path_builder.move_to(p1);
@@ -10,14 +22,184 @@ pub fn get_line_path(p1: Vec2, p2: Vec2) -> Path {
     path_builder.build()
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vector3<f64>,
+    pub max: Vector3<f64>
+}
+
+impl BoundingBox {
+    pub fn from_points(points: impl IntoIterator<Item = Vector3<f64>>) -> Self {
+        points.into_iter().fold(
+            BoundingBox { 
+                min: Vector3::repeat(f64::INFINITY), 
+                max: Vector3::repeat(f64::NEG_INFINITY) 
+            },
+            |bbox, p| BoundingBox { min: bbox.min.inf(&p), max: bbox.max.sup(&p) }
+        )
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox { min: self.min.inf(&other.min), max: self.max.sup(&other.max) }
+    }
+
+    pub fn expanded(&self, margin: f64) -> BoundingBox {
+        BoundingBox { min: self.min.add_scalar(-margin), max: self.max.add_scalar(margin) }
+    }
+
+    pub fn contains(&self, p: &Vector3<f64>) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x &&
+        self.min.y <= p.y && p.y <= self.max.y &&
+        self.min.z <= p.z && p.z <= self.max.z
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x &&
+        self.min.y <= other.max.y && other.min.y <= self.max.y &&
+        self.min.z <= other.max.z && other.min.z <= self.max.z
+    }
+}
+
+// Real roots of a + bt + ct^2
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if c.abs() < 1e-12 {
+        return if b.abs() < 1e-12 { Vec::new() } else { vec![-a / b] };
+    }
+
+    let discriminant = b * b - 4.0 * c * a;
+
+    if discriminant < 0.0 { return Vec::new(); }
+
+    let root = discriminant.sqrt();
+
+    vec![(-b - root) / (2.0 * c), (-b + root) / (2.0 * c)]
+}
+
+// Roots of c0 + c1t + c2t^2 + c3t^3 between 0 and 1, in increasing order
+fn solve_cubic_in_unit_interval([c0, c1, c2, c3]: [f64; 4]) -> Vec<f64> {
+    let f = |t: f64| c0 + t * (c1 + t * (c2 + t * c3));
+
+    // the cubic is monotonic between its turning points, so each piece has at most one root
+    let mut bounds: Vec<f64> = solve_quadratic(c1, 2.0 * c2, 3.0 * c3)
+        .into_iter()
+        .filter(|t| 0.0 < *t && *t < 1.0)
+        .collect();
+    bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    bounds.insert(0, 0.0);
+    bounds.push(1.0);
+
+    let mut roots: Vec<f64> = Vec::new();
+
+    for window in bounds.windows(2) {
+        let (mut low, mut high) = (window[0], window[1]);
+        let (f_low, f_high) = (f(low), f(high));
+
+        let root = if f_low == 0.0 {
+            low
+        } else if f_high == 0.0 {
+            high
+        } else if (f_low < 0.0) != (f_high < 0.0) {
+            for _ in 0..ROOT_BISECTION_ITERATIONS {
+                let mid = (low + high) / 2.0;
+
+                if (f(mid) < 0.0) == (f_low < 0.0) { low = mid; } else { high = mid; }
+            }
+
+            (low + high) / 2.0
+        } else {
+            continue;
+        };
+
+        match roots.last() {
+            Some(last) if root - last < 1e-9 => {},
+            _ => roots.push(root)
+        }
+    }
+
+    roots
+}
+
+// De casteljau's algorithm on a set of bezier control points
+fn split_bezier_points(points: &[Vector3<f64>; 4], t: f64) -> ([Vector3<f64>; 4], [Vector3<f64>; 4]) {
+    let [p1, p2, p3, p4] = *points;
+
+    let p12 = p1.lerp(&p2, t);
+    let p23 = p2.lerp(&p3, t);
+    let p34 = p3.lerp(&p4, t);
+
+    let p123 = p12.lerp(&p23, t);
+    let p234 = p23.lerp(&p34, t);
+
+    let p1234 = p123.lerp(&p234, t);
+
+    ([p1, p12, p123, p1234], [p1234, p234, p34, p4])
+}
+
+// Recursively halves both curves, throwing away pairs of pieces whose control points 
+// (and therefore the pieces themselves) can't overlap
+fn intersect_bezier_points(
+    a: &[Vector3<f64>; 4], 
+    a_range: (f64, f64), 
+    b: &[Vector3<f64>; 4], 
+    b_range: (f64, f64), 
+    depth: usize,
+    intersections: &mut Vec<(f64, f64)>
+) {
+    let a_bbox = BoundingBox::from_points(*a);
+    let b_bbox = BoundingBox::from_points(*b);
+
+    if !a_bbox.intersects(&b_bbox) { return; }
+
+    let a_size = (a_bbox.max - a_bbox.min).max();
+    let b_size = (b_bbox.max - b_bbox.min).max();
+
+    if (a_size < INTERSECTION_TOLERANCE && b_size < INTERSECTION_TOLERANCE) || depth >= INTERSECTION_MAX_DEPTH {
+        let t = ((a_range.0 + a_range.1) / 2.0, (b_range.0 + b_range.1) / 2.0);
+
+        // neighbouring pieces often both touch the same intersection
+        if !intersections.iter().any(|other| (other.0 - t.0).abs() < 1e-3 && (other.1 - t.1).abs() < 1e-3) {
+            intersections.push(t);
+        }
+
+        return;
+    }
+
+    let a_mid = (a_range.0 + a_range.1) / 2.0;
+    let b_mid = (b_range.0 + b_range.1) / 2.0;
+    let (a_left, a_right) = split_bezier_points(a, 0.5);
+    let (b_left, b_right) = split_bezier_points(b, 0.5);
+
+    intersect_bezier_points(&a_left, (a_range.0, a_mid), &b_left, (b_range.0, b_mid), depth + 1, intersections);
+    intersect_bezier_points(&a_left, (a_range.0, a_mid), &b_right, (b_mid, b_range.1), depth + 1, intersections);
+    intersect_bezier_points(&a_right, (a_mid, a_range.1), &b_left, (b_range.0, b_mid), depth + 1, intersections);
+    intersect_bezier_points(&a_right, (a_mid, a_range.1), &b_right, (b_mid, b_range.1), depth + 1, intersections);
+}
+
+#[derive(Debug, Clone)]
 pub struct Curve {
     characteristic_matrix: Matrix4<f64>,
     cached_points_matrix: Matrix4x3<f64>,
+    // Cumulative arc length at t = i / ARC_LENGTH_SAMPLES
+    cached_arc_lengths: Vec<f64>,
     pub points: [Vector3<f64>; 4]
 }
 
 impl Curve {
+    // Builds a curve from any cubic basis, where the rows of the characteristic matrix
+    // are the coefficients of 1, t, t^2 and t^3 for each of the four control points
+    pub fn new(characteristic_matrix: Matrix4<f64>, p1: Vector3<f64>, p2: Vector3<f64>, p3: Vector3<f64>, p4: Vector3<f64>) -> Self {
+        let mut curve = Curve { 
+            characteristic_matrix,
+            cached_points_matrix: Matrix4x3::zeros(),
+            cached_arc_lengths: Vec::with_capacity(ARC_LENGTH_SAMPLES + 1),
+            points: [p1, p2, p3, p4]
+        };
+
+        curve.update_cache();
+
+        curve
+    }
+
     pub fn new_bezier(p1: Vector3<f64>, p2: Vector3<f64>, p3: Vector3<f64>, p4: Vector3<f64>) -> Self {
         let characteristic_matrix = Matrix4::new(
              1.0,  0.0,  0.0, 0.0,
@@ -26,36 +208,51 @@ impl Curve {
             -1.0,  3.0, -3.0, 1.0
         );
 
-        Curve { 
-            characteristic_matrix,
-            cached_points_matrix: characteristic_matrix * Matrix4x3::from_rows(&[
-                p1.transpose(), 
-                p2.transpose(), 
-                p3.transpose(), 
-                p4.transpose()
-            ]),
-            points: [p1, p2, p3, p4]
-        }
+        Self::new(characteristic_matrix, p1, p2, p3, p4)
     }
 
     pub fn new_bspline(p1: Vector3<f64>, p2: Vector3<f64>, p3: Vector3<f64>, p4: Vector3<f64>) -> Self {
         let characteristic_matrix = 1.0/6.0 * Matrix4::new(
-             1.0,  0.0,  0.0, 0.0,
+             1.0,  4.0,  1.0, 0.0,
             -3.0,  0.0,  3.0, 0.0,
              3.0, -6.0,  3.0, 0.0,
             -1.0,  3.0, -3.0, 1.0
         );
 
-        Curve { 
-            characteristic_matrix,
-            cached_points_matrix: characteristic_matrix * Matrix4x3::from_rows(&[
-                p1.transpose(), 
-                p2.transpose(), 
-                p3.transpose(), 
-                p4.transpose()
-            ]),
-            points: [p1, p2, p3, p4]
-        }
+        Self::new(characteristic_matrix, p1, p2, p3, p4)
+    }
+
+    // Goes from p1 to p2, leaving p1 with velocity v1 and arriving at p2 with velocity v2
+    pub fn new_hermite(p1: Vector3<f64>, v1: Vector3<f64>, p2: Vector3<f64>, v2: Vector3<f64>) -> Self {
+        let characteristic_matrix = Matrix4::new(
+             1.0,  0.0,  0.0,  0.0,
+             0.0,  1.0,  0.0,  0.0,
+            -3.0, -2.0,  3.0, -1.0,
+             2.0,  1.0, -2.0,  1.0
+        );
+
+        Self::new(characteristic_matrix, p1, v1, p2, v2)
+    }
+
+    // Goes from p2 to p3, a tension of 0 gives a catmull-rom curve and a tension of 1 gives a straight line
+    pub fn new_cardinal(p1: Vector3<f64>, p2: Vector3<f64>, p3: Vector3<f64>, p4: Vector3<f64>, tension: f64) -> Self {
+        let s = (1.0 - tension) / 2.0;
+        let characteristic_matrix = Matrix4::new(
+                0.0,     1.0,           0.0, 0.0,
+                 -s,     0.0,             s, 0.0,
+            2.0 * s, s - 3.0, 3.0 - 2.0 * s,  -s,
+                 -s, 2.0 - s,       s - 2.0,   s
+        );
+
+        Self::new(characteristic_matrix, p1, p2, p3, p4)
+    }
+
+    pub fn new_catmull_rom(p1: Vector3<f64>, p2: Vector3<f64>, p3: Vector3<f64>, p4: Vector3<f64>) -> Self {
+        Self::new_cardinal(p1, p2, p3, p4, 0.0)
+    }
+
+    pub fn characteristic_matrix(&self) -> &Matrix4<f64> {
+        &self.characteristic_matrix
     }
 
     pub fn set_control_point(&mut self, index: usize, p: Vector3<f64>) {
@@ -63,33 +260,275 @@ impl Curve {
 
         self.points[index] = p;
 
+        self.update_cache();
+    }
+
+    fn update_cache(&mut self) {
         self.cached_points_matrix = self.characteristic_matrix * Matrix4x3::from_rows(&[
             self.points[0].transpose(), 
             self.points[1].transpose(), 
             self.points[2].transpose(), 
             self.points[3].transpose()
         ]);
+
+        self.cached_arc_lengths.clear();
+        self.cached_arc_lengths.push(0.0);
+
+        let mut length = 0.0;
+        let mut last_point = self.get_point(0.0);
+
+        for i in 1..=ARC_LENGTH_SAMPLES {
+            let point = self.get_point(i as f64 / ARC_LENGTH_SAMPLES as f64);
+
+            length += (point - last_point).norm();
+            last_point = point;
+
+            self.cached_arc_lengths.push(length);
+        }
     }
 
     pub fn get_point(&self, t: f64) -> Vector3<f64> {
         (Vector4::new(1.0, t, t.powi(2), t.powi(3)).transpose() * self.cached_points_matrix).transpose()
     }
 
+    pub fn get_derivative(&self, t: f64) -> Vector3<f64> {
+        (Vector4::new(0.0, 1.0, 2.0 * t, 3.0 * t.powi(2)).transpose() * self.cached_points_matrix).transpose()
+    }
+
+    pub fn get_second_derivative(&self, t: f64) -> Vector3<f64> {
+        (Vector4::new(0.0, 0.0, 2.0, 6.0 * t).transpose() * self.cached_points_matrix).transpose()
+    }
+
+    pub fn get_tangent(&self, t: f64) -> Vector3<f64> {
+        self.get_derivative(t).normalize()
+    }
+
+    // The tangent rotated 90 degrees counterclockwise in the xy plane
+    pub fn get_normal(&self, t: f64) -> Vector3<f64> {
+        let tangent = self.get_tangent(t);
+
+        Vector3::new(-tangent.y, tangent.x, 0.0)
+    }
+
+    // Positive when the curve turns counterclockwise, negative when it turns clockwise
+    pub fn get_curvature(&self, t: f64) -> f64 {
+        let d1 = self.get_derivative(t);
+        let d2 = self.get_second_derivative(t);
+        let speed = d1.xy().norm();
+
+        if speed == 0.0 { return 0.0; }
+
+        (d1.x * d2.y - d1.y * d2.x) / speed.powi(3)
+    }
+
+    // The tightest box around the curve, found from where it turns around on each axis
+    pub fn bounding_box(&self) -> BoundingBox {
+        let c = &self.cached_points_matrix;
+        let mut ts = vec![0.0, 1.0];
+
+        for axis in 0..3 {
+            // roots of the derivative along this axis
+            ts.extend(solve_quadratic(c[(1, axis)], 2.0 * c[(2, axis)], 3.0 * c[(3, axis)])
+                .into_iter()
+                .filter(|t| (0.0..=1.0).contains(t)));
+        }
+
+        BoundingBox::from_points(ts.into_iter().map(|t| self.get_point(t)))
+    }
+
+    // Finds the t of the closest point on the curve to p, and how far away it is
+    pub fn project(&self, p: &Vector3<f64>) -> (f64, f64) {
+        let samples: Vec<f64> = (0..=PROJECTION_SAMPLES)
+            .map(|i| (self.get_point(i as f64 / PROJECTION_SAMPLES as f64) - p).norm_squared())
+            .collect();
+
+        let mut best = (0.0, f64::INFINITY);
+
+        for i in 0..=PROJECTION_SAMPLES {
+            // only refine samples that are local minima
+            if 
+                (i > 0 && samples[i - 1] < samples[i]) || 
+                (i < PROJECTION_SAMPLES && samples[i + 1] < samples[i]) 
+            { continue; }
+
+            let mut t = i as f64 / PROJECTION_SAMPLES as f64;
+
+            // newton's method on the derivative of the squared distance
+            for _ in 0..PROJECTION_ITERATIONS {
+                let offset = self.get_point(t) - p;
+                let d1 = self.get_derivative(t);
+                let d2 = self.get_second_derivative(t);
+
+                let numerator = offset.dot(&d1);
+                let denominator = d1.dot(&d1) + offset.dot(&d2);
+
+                if denominator.abs() < 1e-12 { break; }
+
+                t = (t - numerator / denominator).clamp(0.0, 1.0);
+            }
+
+            let distance = (self.get_point(t) - p).norm();
+
+            if distance < best.1 { best = (t, distance); }
+        }
+
+        best
+    }
+
+    pub fn length(&self) -> f64 {
+        self.cached_arc_lengths[ARC_LENGTH_SAMPLES]
+    }
+
+    // Finds the t at which the curve has travelled the given distance, clamped to [0, 1]
+    pub fn t_at_distance(&self, distance: f64) -> f64 {
+        if distance <= 0.0 { return 0.0; }
+        if distance >= self.length() { return 1.0; }
+
+        // index of the first sample that is at least as far as the distance
+        let index = self.cached_arc_lengths.partition_point(|&length| length < distance);
+
+        let prev_length = self.cached_arc_lengths[index - 1];
+        let segment_length = self.cached_arc_lengths[index] - prev_length;
+        let fraction = if segment_length > 0.0 { (distance - prev_length) / segment_length } else { 0.0 };
+
+        ((index - 1) as f64 + fraction) / ARC_LENGTH_SAMPLES as f64
+    }
+
+    pub fn point_at_distance(&self, distance: f64) -> Vector3<f64> {
+        self.get_point(self.t_at_distance(distance))
+    }
+
+    // The control points of the bezier curve with the exact same shape as this curve
+    pub fn to_bezier_points(&self) -> [Vector3<f64>; 4] {
+        // inverse of the bezier characteristic matrix
+        let inverse_bezier_matrix = Matrix4::new(
+            1.0, 0.0,       0.0,       0.0,
+            1.0, 1.0 / 3.0, 0.0,       0.0,
+            1.0, 2.0 / 3.0, 1.0 / 3.0, 0.0,
+            1.0, 1.0,       1.0,       1.0
+        );
+
+        let bezier_points = inverse_bezier_matrix * self.cached_points_matrix;
+
+        [
+            bezier_points.row(0).transpose(),
+            bezier_points.row(1).transpose(),
+            bezier_points.row(2).transpose(),
+            bezier_points.row(3).transpose()
+        ]
+    }
+
+    pub fn to_bezier(&self) -> Curve {
+        let [p1, p2, p3, p4] = self.to_bezier_points();
+
+        Curve::new_bezier(p1, p2, p3, p4)
+    }
+
+    // Uses de casteljau's algorithm to cut the curve in two at t, both halves are bezier curves. 
+    // t is clamped to [0, 1], past the ends de casteljau would extend the curve instead.
+    pub fn split(&self, t: f64) -> (Curve, Curve) {
+        let t = t.clamp(0.0, 1.0);
+        let ([p1, p2, p3, p4], [q1, q2, q3, q4]) = split_bezier_points(&self.to_bezier_points(), t);
+
+        (
+            Curve::new_bezier(p1, p2, p3, p4),
+            Curve::new_bezier(q1, q2, q3, q4)
+        )
+    }
+
+    // The bezier curve that traces this curve from t0 to t1, both clamped to [0, 1]. 
+    // A t1 before t0 gives the single point at t0, same as Spline::sub_spline.
+    pub fn sub_curve(&self, t0: f64, t1: f64) -> Curve {
+        let t0 = t0.clamp(0.0, 1.0);
+        let t1 = t1.clamp(t0, 1.0);
+        let (left, _) = self.split(t1);
+
+        if t1 == 0.0 { return left; }
+
+        let (_, sub_curve) = left.split(t0 / t1);
+
+        sub_curve
+    }
+
+    // Every t where the curve crosses the line segment from a to b, in increasing order
+    pub fn intersect_segment(&self, a: Vec2, b: Vec2) -> Vec<f64> {
+        let direction = b - a;
+        let length_squared = direction.length_squared();
+
+        if length_squared == 0.0 { return Vec::new(); }
+
+        // the curve's distance from the segment's line (scaled by its length) as a cubic in t
+        let normal = Vector3::new(-direction.y as f64, direction.x as f64, 0.0);
+        let start = Vector3::new(a.x as f64, a.y as f64, 0.0);
+        let c = &self.cached_points_matrix;
+        let coefficients = [
+            normal.dot(&(c.row(0).transpose() - start)),
+            normal.dot(&c.row(1).transpose()),
+            normal.dot(&c.row(2).transpose()),
+            normal.dot(&c.row(3).transpose())
+        ];
+
+        solve_cubic_in_unit_interval(coefficients)
+            .into_iter()
+            .filter(|t| {
+                let point = self.get_point(*t);
+                let along = Vec2::new((point.x - start.x) as f32, (point.y - start.y) as f32).dot(direction) / length_squared;
+
+                (0.0..=1.0).contains(&along)
+            })
+            .collect()
+    }
+
+    // Every t where the curve crosses an edge of the polygon, in increasing order
+    pub fn intersect_polygon(&self, polygon: &[Vec2]) -> Vec<f64> {
+        let mut ts: Vec<f64> = (0..polygon.len())
+            .flat_map(|i| self.intersect_segment(polygon[i], polygon[(i + 1) % polygon.len()]))
+            .collect();
+
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+
+        ts
+    }
+
+    // Every pair of (t on this curve, t on the other curve) where the curves cross
+    pub fn intersect_curve(&self, other: &Curve) -> Vec<(f64, f64)> {
+        let mut intersections = Vec::new();
+
+        intersect_bezier_points(
+            &self.to_bezier_points(), 
+            (0.0, 1.0), 
+            &other.to_bezier_points(), 
+            (0.0, 1.0), 
+            0, 
+            &mut intersections
+        );
+
+        intersections.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        intersections
+    }
+
     pub fn to_bezier_path(&self) -> Path {
         let mut path_builder = PathBuilder::new();
-        path_builder.move_to(Vec2::new(self.points[0].x as f32, self.points[0].y as f32));
+        let [p1, p2, p3, p4] = self.to_bezier_points();
+
+        path_builder.move_to(Vec2::new(p1.x as f32, p1.y as f32));
         path_builder.cubic_bezier_to(
-            Vec2::new(self.points[1].x as f32, self.points[1].y as f32), 
-            Vec2::new(self.points[2].x as f32, self.points[2].y as f32), 
-            Vec2::new(self.points[3].x as f32, self.points[3].y as f32)
+            Vec2::new(p2.x as f32, p2.y as f32), 
+            Vec2::new(p3.x as f32, p3.y as f32), 
+            Vec2::new(p4.x as f32, p4.y as f32)
         );
 
         path_builder.build()
     }
 }
 
+type CurveConstructor = fn(Vector3<f64>, Vector3<f64>, Vector3<f64>, Vector3<f64>) -> Curve;
+
 pub struct Spline {
-    curves: Vec<Curve>
+    curves: Vec<Curve>,
+    closed: bool
 }
 
 impl Spline {
@@ -111,26 +550,815 @@ impl Spline {
                     ))
                 };
 
-                Ok(Self { curves })
+                Ok(Self { curves, closed: false })
             }
         }
     }
 
+    // Same as new_bezier, but the last curve ends back at the first point
+    pub fn new_bezier_loop(points: Vec<Vector3<f64>>) -> Result<Self, String> {
+        match points.len() {
+            0 => Err("Cannot create a spline without points".to_string()),
+            n if n % 3 != 0 => Err(format!("Cannot create a looping bezier spline with {} points", n)),
+            n => {
+                let mut curves = Vec::new();
+
+                for i in 0..(n / 3) {
+                    curves.push(Curve::new_bezier(
+                        points[3 * i], 
+                        points[3 * i + 1], 
+                        points[3 * i + 2], 
+                        points[(3 * i + 3) % n]
+                    ))
+                };
+
+                Ok(Self { curves, closed: true })
+            }
+        }
+    }
+
+    pub fn new_bspline(points: Vec<Vector3<f64>>) -> Result<Self, String> {
+        Self::from_sliding_window(points, false, Curve::new_bspline)
+            .map_err(|n| format!("Cannot create a b-spline with {} points, it needs at least four", n))
+    }
+
+    pub fn new_bspline_loop(points: Vec<Vector3<f64>>) -> Result<Self, String> {
+        Self::from_sliding_window(points, true, Curve::new_bspline)
+            .map_err(|n| format!("Cannot create a looping b-spline with {} points, it needs at least three", n))
+    }
+
+    // Passes through every point except the first and last, which only shape the ends
+    pub fn new_catmull_rom(points: Vec<Vector3<f64>>) -> Result<Self, String> {
+        Self::from_sliding_window(points, false, Curve::new_catmull_rom)
+            .map_err(|n| format!("Cannot create a catmull-rom spline with {} points, it needs at least four", n))
+    }
+
+    // Passes through every point and loops back around to the first
+    pub fn new_catmull_rom_loop(points: Vec<Vector3<f64>>) -> Result<Self, String> {
+        Self::from_sliding_window(points, true, Curve::new_catmull_rom)
+            .map_err(|n| format!("Cannot create a looping catmull-rom spline with {} points, it needs at least three", n))
+    }
+
+    // Makes one curve out of every four consecutive points, wrapping around the end of 
+    // the points when closed. Returns the number of points if there aren't enough of them.
+    fn from_sliding_window(
+        points: Vec<Vector3<f64>>, 
+        closed: bool,
+        new_curve: CurveConstructor
+    ) -> Result<Self, usize> {
+        let n = points.len();
+
+        if (closed && n < 3) || (!closed && n < 4) { return Err(n); }
+
+        let num_curves = if closed { n } else { n - 3 };
+        let curves = (0..num_curves).map(|i| new_curve(
+            points[i], 
+            points[(i + 1) % n], 
+            points[(i + 2) % n], 
+            points[(i + 3) % n]
+        )).collect();
+
+        Ok(Self { curves, closed })
+    }
+
     pub fn size(&self) -> usize {
         self.curves.len()
     }
 
-    pub fn get_point(&self, t: f64) -> Vector3<f64> {
-        let mut index = t.floor() as isize;
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
 
-        index = if index < 0 { 
+    // Splits a spline t into the index of its curve and the t within that curve. 
+    // Open splines clamp to their first and last curves, closed ones wrap around.
+    fn get_local_t(&self, t: f64) -> (usize, f64) {
+        let size = self.curves.len();
+
+        if self.closed {
+            let t = t.rem_euclid(size as f64);
+            let index = (t.floor() as usize).min(size - 1);
+
+            return (index, t - index as f64);
+        }
+
+        let index = t.floor() as isize;
+        let index = if index < 0 { 
             0 
-        } else if index >= self.curves.len() as isize { 
-            self.curves.len() as isize - 1 
+        } else if index >= size as isize { 
+            size - 1 
         } else { 
-            index 
+            index as usize
         };
 
-        self.curves[index as usize].get_point(t - (index as f64))
+        (index, t - index as f64)
+    }
+
+    pub fn get_point(&self, t: f64) -> Vector3<f64> {
+        let (index, t) = self.get_local_t(t);
+
+        self.curves[index].get_point(t)
+    }
+
+    pub fn get_derivative(&self, t: f64) -> Vector3<f64> {
+        let (index, t) = self.get_local_t(t);
+
+        self.curves[index].get_derivative(t)
+    }
+
+    pub fn get_second_derivative(&self, t: f64) -> Vector3<f64> {
+        let (index, t) = self.get_local_t(t);
+
+        self.curves[index].get_second_derivative(t)
     }
-}
\ No newline at end of file
+
+    pub fn get_tangent(&self, t: f64) -> Vector3<f64> {
+        let (index, t) = self.get_local_t(t);
+
+        self.curves[index].get_tangent(t)
+    }
+
+    pub fn get_normal(&self, t: f64) -> Vector3<f64> {
+        let (index, t) = self.get_local_t(t);
+
+        self.curves[index].get_normal(t)
+    }
+
+    pub fn get_curvature(&self, t: f64) -> f64 {
+        let (index, t) = self.get_local_t(t);
+
+        self.curves[index].get_curvature(t)
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        self.curves
+            .iter()
+            .map(Curve::bounding_box)
+            .reduce(|a, b| a.union(&b))
+            .unwrap()
+    }
+
+    // Same as Curve::project, but the returned t is in the range [0, size]
+    pub fn project(&self, p: &Vector3<f64>) -> (f64, f64) {
+        self.curves
+            .iter()
+            .enumerate()
+            .map(|(i, curve)| {
+                let (t, distance) = curve.project(p);
+
+                (i as f64 + t, distance)
+            })
+            .fold((0.0, f64::INFINITY), |best, candidate| if candidate.1 < best.1 { candidate } else { best })
+    }
+
+    pub fn length(&self) -> f64 {
+        self.curves.iter().map(Curve::length).sum()
+    }
+
+    // Same as Curve::t_at_distance, but the returned t is in the range [0, size]. 
+    // Closed splines wrap the distance around instead of clamping it.
+    pub fn t_at_distance(&self, mut distance: f64) -> f64 {
+        if self.closed {
+            let length = self.length();
+
+            // there's nowhere to go around a loop with no length, and wrapping would divide by zero
+            if length == 0.0 { return 0.0; }
+
+            distance = distance.rem_euclid(length);
+        }
+
+        for (i, curve) in self.curves.iter().enumerate() {
+            let length = curve.length();
+
+            if distance <= length || i == self.curves.len() - 1 {
+                return i as f64 + curve.t_at_distance(distance);
+            }
+
+            distance -= length;
+        }
+
+        0.0
+    }
+
+    pub fn point_at_distance(&self, distance: f64) -> Vector3<f64> {
+        self.get_point(self.t_at_distance(distance))
+    }
+
+    // Same as Curve::intersect_polygon, but the returned ts are in the range [0, size]
+    pub fn intersect_polygon(&self, polygon: &[Vec2]) -> Vec<f64> {
+        let mut ts: Vec<f64> = self.curves
+            .iter()
+            .enumerate()
+            .flat_map(|(i, curve)| curve.intersect_polygon(polygon).into_iter().map(move |t| i as f64 + t))
+            .collect();
+
+        // a crossing right on a joint gets found by the curves on both sides of it
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+
+        ts
+    }
+
+    // Same as Curve::intersect_curve, but the ts on this spline are in the range [0, size]
+    pub fn intersect_curve(&self, other: &Curve) -> Vec<(f64, f64)> {
+        self.curves
+            .iter()
+            .enumerate()
+            .flat_map(|(i, curve)| curve.intersect_curve(other).into_iter().map(move |(t, u)| (i as f64 + t, u)))
+            .collect()
+    }
+
+    // Cuts the spline in two at t, neither half is closed
+    pub fn split(&self, t: f64) -> (Spline, Spline) {
+        (self.sub_spline(0.0, t), self.sub_spline(t, self.curves.len() as f64))
+    }
+
+    // The open spline that traces this spline from t0 to t1, both clamped to [0, size]
+    pub fn sub_spline(&self, t0: f64, t1: f64) -> Spline {
+        let size = self.curves.len();
+        let t0 = t0.clamp(0.0, size as f64);
+        let t1 = t1.clamp(t0, size as f64);
+
+        // t1 on the boundary between two curves belongs to the end of the first one
+        let index0 = (t0.floor() as usize).min(size - 1);
+        let index1 = if t1 > t0 && t1.fract() == 0.0 { t1 as usize - 1 } else { (t1.floor() as usize).min(size - 1) };
+
+        let local_t0 = t0 - index0 as f64;
+        let local_t1 = t1 - index1 as f64;
+
+        let curves = if index0 == index1 {
+            vec![self.curves[index0].sub_curve(local_t0, local_t1)]
+        } else {
+            let mut curves = vec![self.curves[index0].sub_curve(local_t0, 1.0)];
+            curves.extend(self.curves[(index0 + 1)..index1].iter().cloned());
+            curves.push(self.curves[index1].sub_curve(0.0, local_t1));
+
+            curves
+        };
+
+        Spline { curves, closed: false }
+    }
+
+    pub fn to_bezier_path(&self) -> Path {
+        let mut path_builder = PathBuilder::new();
+        let [start, ..] = self.curves[0].to_bezier_points();
+
+        path_builder.move_to(Vec2::new(start.x as f32, start.y as f32));
+
+        for curve in self.curves.iter() {
+            let [_, p2, p3, p4] = curve.to_bezier_points();
+
+            path_builder.cubic_bezier_to(
+                Vec2::new(p2.x as f32, p2.y as f32), 
+                Vec2::new(p3.x as f32, p3.y as f32), 
+                Vec2::new(p4.x as f32, p4.y as f32)
+            );
+        }
+
+        if self.closed { path_builder.close(); }
+
+        path_builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f64, y: f64) -> Vector3<f64> {
+        Vector3::new(x, y, 0.0)
+    }
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() <= tolerance, "{} is not within {} of {}", a, tolerance, b);
+    }
+
+    fn assert_points_close(a: Vector3<f64>, b: Vector3<f64>, tolerance: f64) {
+        assert!((a - b).norm() <= tolerance, "{:?} is not within {} of {:?}", a, tolerance, b);
+    }
+
+    // A straight line whose control points are bunched up at the start, so t moves unevenly along it
+    fn uneven_line() -> Curve {
+        Curve::new_bezier(point(0.0, 0.0), point(10.0, 0.0), point(20.0, 0.0), point(300.0, 0.0))
+    }
+
+    #[test]
+    fn line_length() {
+        assert_close(uneven_line().length(), 300.0, 1e-9);
+    }
+
+    #[test]
+    fn quarter_circle_length() {
+        // the usual bezier approximation of a quarter of a circle with radius 100
+        let k = 100.0 * 0.552284749831;
+        let curve = Curve::new_bezier(point(100.0, 0.0), point(100.0, k), point(k, 100.0), point(0.0, 100.0));
+
+        assert_close(curve.length(), 100.0 * std::f64::consts::FRAC_PI_2, 0.1);
+    }
+
+    #[test]
+    fn distance_is_even_along_uneven_curve() {
+        let curve = uneven_line();
+
+        // raw t is nowhere near halfway along
+        assert!(curve.get_point(0.5).x < 100.0);
+
+        for distance in [0.0, 50.0, 150.0, 250.0, 300.0] {
+            assert_close(curve.point_at_distance(distance).x, distance, 0.5);
+        }
+    }
+
+    #[test]
+    fn t_at_distance_clamps() {
+        let curve = uneven_line();
+
+        assert_eq!(curve.t_at_distance(-10.0), 0.0);
+        assert_eq!(curve.t_at_distance(0.0), 0.0);
+        assert_eq!(curve.t_at_distance(curve.length()), 1.0);
+        assert_eq!(curve.t_at_distance(1000.0), 1.0);
+    }
+
+    #[test]
+    fn set_control_point_updates_length() {
+        let mut curve = uneven_line();
+        curve.set_control_point(3, point(600.0, 0.0));
+
+        assert_close(curve.length(), 600.0, 1e-9);
+        assert_close(curve.point_at_distance(450.0).x, 450.0, 1.0);
+    }
+
+    #[test]
+    fn spline_distances() {
+        let spline = Spline::new_bezier(vec![
+            point(0.0, 0.0), point(10.0, 0.0), point(20.0, 0.0), point(100.0, 0.0),
+            point(100.0, 50.0), point(100.0, 100.0), point(100.0, 200.0)
+        ]).unwrap();
+
+        assert_close(spline.length(), 300.0, 1e-9);
+        assert_points_close(spline.point_at_distance(50.0), point(50.0, 0.0), 0.5);
+        assert_points_close(spline.point_at_distance(200.0), point(100.0, 100.0), 0.5);
+        assert_close(spline.t_at_distance(100.0), 1.0, 1e-9);
+        assert_close(spline.t_at_distance(1000.0), 2.0, 1e-9);
+    }
+
+    #[test]
+    fn closed_spline_distances_wrap() {
+        let spline = Spline::new_bezier_loop(vec![
+            point(0.0, 0.0), point(10.0, 0.0), point(20.0, 0.0),
+            point(30.0, 0.0), point(20.0, 0.0), point(10.0, 0.0)
+        ]).unwrap();
+
+        assert_close(spline.length(), 60.0, 1e-9);
+        assert_close(spline.t_at_distance(75.0), spline.t_at_distance(15.0), 1e-9);
+        assert_close(spline.t_at_distance(-15.0), spline.t_at_distance(45.0), 1e-9);
+    }
+
+    #[test]
+    fn zero_length_closed_spline() {
+        let spline = Spline::new_bezier_loop(vec![point(5.0, 5.0); 6]).unwrap();
+
+        assert_eq!(spline.length(), 0.0);
+        assert_eq!(spline.t_at_distance(0.0), 0.0);
+        assert_eq!(spline.t_at_distance(10.0), 0.0);
+        assert_points_close(spline.point_at_distance(10.0), point(5.0, 5.0), 1e-12);
+    }
+
+    #[test]
+    fn bezier_touches_its_ends() {
+        let curve = Curve::new_bezier(point(0.0, 0.0), point(1.0, 2.0), point(3.0, 2.0), point(4.0, 0.0));
+
+        assert_points_close(curve.get_point(0.0), point(0.0, 0.0), 1e-12);
+        assert_points_close(curve.get_point(1.0), point(4.0, 0.0), 1e-12);
+        assert_points_close(curve.get_derivative(0.0), point(3.0, 6.0), 1e-12);
+        assert_points_close(curve.get_derivative(1.0), point(3.0, -6.0), 1e-12);
+    }
+
+    #[test]
+    fn hermite_interpolates_points_and_velocities() {
+        let (p1, v1, p2, v2) = (point(0.0, 0.0), point(5.0, 1.0), point(10.0, 3.0), point(-2.0, 4.0));
+        let curve = Curve::new_hermite(p1, v1, p2, v2);
+
+        assert_points_close(curve.get_point(0.0), p1, 1e-12);
+        assert_points_close(curve.get_point(1.0), p2, 1e-12);
+        assert_points_close(curve.get_derivative(0.0), v1, 1e-12);
+        assert_points_close(curve.get_derivative(1.0), v2, 1e-12);
+    }
+
+    #[test]
+    fn cardinal_goes_through_middle_points() {
+        let [p1, p2, p3, p4] = [point(0.0, 0.0), point(2.0, 4.0), point(6.0, 4.0), point(8.0, 0.0)];
+
+        for tension in [0.0, 0.5, 1.0] {
+            let curve = Curve::new_cardinal(p1, p2, p3, p4, tension);
+            let s = (1.0 - tension) / 2.0;
+
+            assert_points_close(curve.get_point(0.0), p2, 1e-12);
+            assert_points_close(curve.get_point(1.0), p3, 1e-12);
+            assert_points_close(curve.get_derivative(0.0), s * (p3 - p1), 1e-12);
+            assert_points_close(curve.get_derivative(1.0), s * (p4 - p2), 1e-12);
+        }
+
+        // full tension is a straight line from p2 to p3
+        let straight = Curve::new_cardinal(p1, p2, p3, p4, 1.0);
+        assert_points_close(straight.get_point(0.5), p2.lerp(&p3, 0.5), 1e-12);
+    }
+
+    #[test]
+    fn catmull_rom_is_cardinal_without_tension() {
+        let [p1, p2, p3, p4] = [point(0.0, 0.0), point(2.0, 4.0), point(6.0, 4.0), point(8.0, 0.0)];
+        let catmull_rom = Curve::new_catmull_rom(p1, p2, p3, p4);
+        let cardinal = Curve::new_cardinal(p1, p2, p3, p4, 0.0);
+
+        for t in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            assert_points_close(catmull_rom.get_point(t), cardinal.get_point(t), 1e-12);
+        }
+
+        assert_points_close(catmull_rom.get_derivative(0.0), (p3 - p1) / 2.0, 1e-12);
+    }
+
+    #[test]
+    fn bspline_only_approximates_its_points() {
+        let [p1, p2, p3, p4] = [point(0.0, 0.0), point(2.0, 4.0), point(6.0, 4.0), point(8.0, 0.0)];
+        let curve = Curve::new_bspline(p1, p2, p3, p4);
+
+        assert_points_close(curve.get_point(0.0), (p1 + 4.0 * p2 + p3) / 6.0, 1e-12);
+        assert_points_close(curve.get_point(1.0), (p2 + 4.0 * p3 + p4) / 6.0, 1e-12);
+        assert!((curve.get_point(0.0) - p2).norm() > 0.1);
+    }
+
+    fn zigzag(n: usize) -> Vec<Vector3<f64>> {
+        (0..n).map(|i| point(10.0 * i as f64, if i % 2 == 0 { 0.0 } else { 10.0 })).collect()
+    }
+
+    #[test]
+    fn spline_constructor_sizes() {
+        assert!(Spline::new_bezier(Vec::new()).is_err());
+        assert!(Spline::new_bezier(zigzag(3)).is_err());
+        assert!(Spline::new_bezier(zigzag(5)).is_err());
+        assert_eq!(Spline::new_bezier(zigzag(7)).unwrap().size(), 2);
+
+        assert!(Spline::new_bezier_loop(zigzag(4)).is_err());
+        assert_eq!(Spline::new_bezier_loop(zigzag(6)).unwrap().size(), 2);
+
+        assert!(Spline::new_bspline(zigzag(3)).is_err());
+        assert_eq!(Spline::new_bspline(zigzag(5)).unwrap().size(), 2);
+        assert!(Spline::new_bspline_loop(zigzag(2)).is_err());
+        assert_eq!(Spline::new_bspline_loop(zigzag(3)).unwrap().size(), 3);
+
+        assert!(Spline::new_catmull_rom(zigzag(3)).is_err());
+        assert_eq!(Spline::new_catmull_rom(zigzag(6)).unwrap().size(), 3);
+        assert!(Spline::new_catmull_rom_loop(zigzag(2)).is_err());
+
+        assert!(!Spline::new_bezier(zigzag(7)).unwrap().is_closed());
+        assert!(Spline::new_bezier_loop(zigzag(6)).unwrap().is_closed());
+    }
+
+    #[test]
+    fn bezier_splines_go_through_every_third_point() {
+        let points = zigzag(7);
+        let spline = Spline::new_bezier(points.clone()).unwrap();
+
+        for i in 0..=2 {
+            assert_points_close(spline.get_point(i as f64), points[3 * i], 1e-12);
+        }
+
+        // a loop ends back where it started
+        let points = zigzag(6);
+        let spline = Spline::new_bezier_loop(points.clone()).unwrap();
+        assert_points_close(spline.get_point(1.0), points[3], 1e-12);
+        assert_points_close(spline.get_point(2.0 - 1e-9), points[0], 1e-6);
+    }
+
+    #[test]
+    fn catmull_rom_splines_go_through_their_points() {
+        let points = zigzag(6);
+        let spline = Spline::new_catmull_rom(points.clone()).unwrap();
+
+        // every point but the first and last
+        for i in 0..=3 {
+            assert_points_close(spline.get_point(i as f64), points[i + 1], 1e-12);
+        }
+
+        let spline = Spline::new_catmull_rom_loop(points.clone()).unwrap();
+
+        // every point, wrapping around
+        for i in 0..=6 {
+            assert_points_close(spline.get_point(i as f64), points[(i + 1) % 6], 1e-9);
+        }
+    }
+
+    #[test]
+    fn bspline_splines_join_smoothly() {
+        let spline = Spline::new_bspline(zigzag(6)).unwrap();
+
+        for i in 1..spline.size() {
+            let t = i as f64;
+
+            assert_points_close(spline.get_point(t - 1e-9), spline.get_point(t), 1e-6);
+            assert_points_close(spline.get_derivative(t - 1e-9), spline.get_derivative(t), 1e-6);
+            assert_points_close(spline.get_second_derivative(t - 1e-9), spline.get_second_derivative(t), 1e-6);
+        }
+    }
+
+    // One curve of every basis, all built from the same four points
+    fn every_basis() -> Vec<(&'static str, Curve)> {
+        let [p1, p2, p3, p4] = [point(0.0, 0.0), point(2.0, 4.0), point(6.0, 4.0), point(8.0, 0.0)];
+
+        vec![
+            ("bezier", Curve::new_bezier(p1, p2, p3, p4)),
+            ("b-spline", Curve::new_bspline(p1, p2, p3, p4)),
+            ("hermite", Curve::new_hermite(p1, p2, p3, p4)),
+            ("cardinal", Curve::new_cardinal(p1, p2, p3, p4, 0.3)),
+            ("catmull-rom", Curve::new_catmull_rom(p1, p2, p3, p4))
+        ]
+    }
+
+    #[test]
+    fn bezier_conversion_keeps_the_shape() {
+        for (name, curve) in every_basis() {
+            let bezier = curve.to_bezier();
+
+            for i in 0..=10 {
+                let t = i as f64 / 10.0;
+
+                assert!(
+                    (bezier.get_point(t) - curve.get_point(t)).norm() < 1e-9, 
+                    "the {} curve and its bezier differ at t = {}", name, t
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bezier_conversion_of_a_bezier_is_itself() {
+        let points = [point(0.0, 0.0), point(2.0, 4.0), point(6.0, 4.0), point(8.0, 0.0)];
+        let curve = Curve::new_bezier(points[0], points[1], points[2], points[3]);
+
+        for (converted, original) in curve.to_bezier_points().into_iter().zip(points) {
+            assert_points_close(converted, original, 1e-12);
+        }
+    }
+
+    fn wavy_curve() -> Curve {
+        Curve::new_catmull_rom(point(0.0, 0.0), point(2.0, 4.0), point(6.0, -4.0), point(8.0, 0.0))
+    }
+
+    #[test]
+    fn split_halves_meet() {
+        let curve = wavy_curve();
+
+        for t in [0.0, 0.3, 0.5, 0.9, 1.0] {
+            let (left, right) = curve.split(t);
+
+            assert_points_close(left.get_point(1.0), curve.get_point(t), 1e-9);
+            assert_points_close(right.get_point(0.0), curve.get_point(t), 1e-9);
+            assert_points_close(left.get_point(0.0), curve.get_point(0.0), 1e-9);
+            assert_points_close(right.get_point(1.0), curve.get_point(1.0), 1e-9);
+
+            // each half traces its own stretch of the curve
+            for u in [0.25, 0.5, 0.75] {
+                assert_points_close(left.get_point(u), curve.get_point(u * t), 1e-9);
+                assert_points_close(right.get_point(u), curve.get_point(t + u * (1.0 - t)), 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn split_clamps_t() {
+        let curve = wavy_curve();
+        let (left, right) = curve.split(1.5);
+
+        assert_points_close(left.get_point(1.0), curve.get_point(1.0), 1e-9);
+        assert_points_close(right.get_point(0.5), curve.get_point(1.0), 1e-9);
+    }
+
+    #[test]
+    fn sub_curve_traces_the_range() {
+        let curve = wavy_curve();
+        let sub_curve = curve.sub_curve(0.2, 0.7);
+
+        for u in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            assert_points_close(sub_curve.get_point(u), curve.get_point(0.2 + u * 0.5), 1e-9);
+        }
+
+        // starting at 0 skips the second split
+        let start = curve.sub_curve(0.0, 0.4);
+        assert_points_close(start.get_point(1.0), curve.get_point(0.4), 1e-9);
+    }
+
+    #[test]
+    fn sub_curve_clamps_its_range() {
+        let curve = wavy_curve();
+
+        let past_the_ends = curve.sub_curve(-0.5, 1.5);
+        for u in [0.0, 0.5, 1.0] {
+            assert_points_close(past_the_ends.get_point(u), curve.get_point(u), 1e-9);
+        }
+
+        let backwards = curve.sub_curve(0.7, 0.2);
+        assert_points_close(backwards.get_point(0.0), curve.get_point(0.7), 1e-9);
+        assert_points_close(backwards.get_point(1.0), curve.get_point(0.7), 1e-9);
+    }
+
+    #[test]
+    fn spline_split_halves_meet() {
+        let spline = Spline::new_catmull_rom(zigzag(6)).unwrap();
+        let (left, right) = spline.split(1.5);
+
+        assert_eq!(left.size(), 2);
+        assert_eq!(right.size(), 2);
+        assert_points_close(left.get_point(left.size() as f64), spline.get_point(1.5), 1e-9);
+        assert_points_close(right.get_point(0.0), spline.get_point(1.5), 1e-9);
+        assert_points_close(right.get_point(right.size() as f64), spline.get_point(3.0), 1e-9);
+    }
+
+    // Goes up from (0, 0) and comes back down at (10, 0), highest at t = 0.5
+    fn arch() -> Curve {
+        Curve::new_bezier(point(0.0, 0.0), point(0.0, 10.0), point(10.0, 10.0), point(10.0, 0.0))
+    }
+
+    #[test]
+    fn bounding_box_finds_turning_points() {
+        let bbox = arch().bounding_box();
+
+        // the top of the arch is nowhere near the ends or the control points
+        assert_points_close(bbox.min, point(0.0, 0.0), 1e-9);
+        assert_points_close(bbox.max, point(10.0, 7.5), 1e-9);
+    }
+
+    #[test]
+    fn bounding_box_is_tight() {
+        // overshoots on both sides in x, and the ends are in the middle of the box
+        let curve = Curve::new_bezier(point(0.0, 0.0), point(20.0, 5.0), point(-20.0, 5.0), point(0.0, 10.0));
+        let bbox = curve.bounding_box();
+        let samples: Vec<Vector3<f64>> = (0..=1000).map(|i| curve.get_point(i as f64 / 1000.0)).collect();
+
+        assert!(samples.iter().all(|p| bbox.expanded(1e-9).contains(p)));
+        assert!(bbox.min.x < -1.0 && bbox.max.x > 1.0);
+
+        for axis in 0..2 {
+            let min = samples.iter().map(|p| p[axis]).fold(f64::INFINITY, f64::min);
+            let max = samples.iter().map(|p| p[axis]).fold(f64::NEG_INFINITY, f64::max);
+
+            assert_close(bbox.min[axis], min, 1e-3);
+            assert_close(bbox.max[axis], max, 1e-3);
+        }
+    }
+
+    #[test]
+    fn project_onto_the_middle() {
+        let (t, distance) = arch().project(&point(5.0, 20.0));
+
+        assert_close(t, 0.5, 1e-6);
+        assert_close(distance, 12.5, 1e-6);
+    }
+
+    #[test]
+    fn project_onto_the_ends() {
+        let curve = arch();
+
+        let (t, distance) = curve.project(&point(-5.0, -5.0));
+        assert_eq!(t, 0.0);
+        assert_close(distance, 50.0_f64.sqrt(), 1e-9);
+
+        let (t, distance) = curve.project(&point(20.0, -3.0));
+        assert_eq!(t, 1.0);
+        assert_close(distance, 109.0_f64.sqrt(), 1e-9);
+    }
+
+    #[test]
+    fn project_a_point_on_the_curve() {
+        let curve = arch();
+
+        for t in [0.1, 0.3, 0.8] {
+            let (projected_t, distance) = curve.project(&curve.get_point(t));
+
+            assert_close(projected_t, t, 1e-6);
+            assert_close(distance, 0.0, 1e-6);
+        }
+    }
+
+    fn assert_crossings_on_line(curve: &Curve, ts: &[f64], y: f64) {
+        for t in ts {
+            assert_close(curve.get_point(*t).y, y, 1e-6);
+        }
+    }
+
+    #[test]
+    fn segment_crossing_twice() {
+        let curve = arch();
+        let ts = curve.intersect_segment(Vec2::new(-5.0, 5.0), Vec2::new(15.0, 5.0));
+
+        assert_eq!(ts.len(), 2);
+        assert!(ts[0] < 0.5 && ts[1] > 0.5);
+        assert_crossings_on_line(&curve, &ts, 5.0);
+    }
+
+    #[test]
+    fn segment_touching_the_top() {
+        let curve = arch();
+        let ts = curve.intersect_segment(Vec2::new(0.0, 7.5), Vec2::new(10.0, 7.5));
+
+        assert_eq!(ts.len(), 1);
+        assert_close(ts[0], 0.5, 1e-6);
+    }
+
+    #[test]
+    fn segment_missing() {
+        let curve = arch();
+
+        // entirely above the arch
+        assert!(curve.intersect_segment(Vec2::new(0.0, 8.0), Vec2::new(10.0, 8.0)).is_empty());
+        // its line crosses the arch, but the segment stops short of it
+        assert!(curve.intersect_segment(Vec2::new(-10.0, 5.0), Vec2::new(-1.0, 5.0)).is_empty());
+        // a single point
+        assert!(curve.intersect_segment(Vec2::new(5.0, 7.5), Vec2::new(5.0, 7.5)).is_empty());
+    }
+
+    #[test]
+    fn segment_parallel_to_a_straight_curve() {
+        let line = Curve::new_bezier(point(0.0, 0.0), point(3.0, 0.0), point(6.0, 0.0), point(10.0, 0.0));
+
+        assert!(line.intersect_segment(Vec2::new(0.0, 1.0), Vec2::new(10.0, 1.0)).is_empty());
+        assert!(line.intersect_segment(Vec2::new(20.0, 0.0), Vec2::new(30.0, 0.0)).is_empty());
+    }
+
+    #[test]
+    fn polygon_crossings_are_sorted() {
+        let curve = arch();
+        let square = vec![Vec2::new(2.0, 2.0), Vec2::new(8.0, 2.0), Vec2::new(8.0, 20.0), Vec2::new(2.0, 20.0)];
+        let ts = curve.intersect_polygon(&square);
+
+        // in through the left side and out through the right
+        assert_eq!(ts.len(), 2);
+        assert!(ts[0] < ts[1]);
+        assert_close(curve.get_point(ts[0]).x, 2.0, 1e-6);
+        assert_close(curve.get_point(ts[1]).x, 8.0, 1e-6);
+
+        let far_away = vec![Vec2::new(100.0, 100.0), Vec2::new(110.0, 100.0), Vec2::new(110.0, 110.0)];
+        assert!(curve.intersect_polygon(&far_away).is_empty());
+    }
+
+    #[test]
+    fn spline_crossing_at_a_joint_counts_once() {
+        // two straight curves meeting at (30, 0), with t moving evenly along each
+        let spline = Spline::new_bezier(vec![
+            point(0.0, 0.0), point(10.0, 0.0), point(20.0, 0.0), point(30.0, 0.0),
+            point(40.0, 0.0), point(50.0, 0.0), point(60.0, 0.0)
+        ]).unwrap();
+        let square = vec![Vec2::new(30.0, -5.0), Vec2::new(45.0, -5.0), Vec2::new(45.0, 5.0), Vec2::new(30.0, 5.0)];
+        let ts = spline.intersect_polygon(&square);
+
+        assert_eq!(ts.len(), 2);
+        assert_close(ts[0], 1.0, 1e-6);
+        assert_close(ts[1], 1.5, 1e-6);
+    }
+
+    fn assert_intersections_meet(a: &Curve, b: &Curve, intersections: &[(f64, f64)]) {
+        for (t, u) in intersections {
+            assert_points_close(a.get_point(*t), b.get_point(*u), 1e-3);
+        }
+    }
+
+    #[test]
+    fn curves_crossing_each_other() {
+        let curve = arch();
+        let line = Curve::new_bezier(point(-5.0, 5.0), point(0.0, 5.0), point(10.0, 5.0), point(15.0, 5.0));
+        let intersections = curve.intersect_curve(&line);
+
+        assert_eq!(intersections.len(), 2);
+        assert!(intersections[0].0 < intersections[1].0);
+        assert_intersections_meet(&curve, &line, &intersections);
+    }
+
+    #[test]
+    fn curves_touching() {
+        let curve = arch();
+        let line = Curve::new_bezier(point(0.0, 7.5), point(3.0, 7.5), point(6.0, 7.5), point(10.0, 7.5));
+        let intersections = curve.intersect_curve(&line);
+
+        assert!(!intersections.is_empty());
+        assert!(intersections.iter().all(|(t, _)| (t - 0.5).abs() < 0.01));
+    }
+
+    #[test]
+    fn curves_missing() {
+        let curve = arch();
+        let above = Curve::new_bezier(point(0.0, 8.0), point(3.0, 8.0), point(6.0, 8.0), point(10.0, 8.0));
+        let far_away = Curve::new_bezier(point(100.0, 0.0), point(100.0, 10.0), point(110.0, 10.0), point(110.0, 0.0));
+
+        assert!(curve.intersect_curve(&above).is_empty());
+        assert!(curve.intersect_curve(&far_away).is_empty());
+    }
+
+    #[test]
+    fn curve_crossing_itself() {
+        // the control points cross over, which ties the curve in a loop
+        let curve = Curve::new_bezier(point(0.0, 0.0), point(15.0, 10.0), point(-5.0, 10.0), point(10.0, 0.0));
+        let (first_half, second_half) = curve.split(0.5);
+        let intersections = first_half.intersect_curve(&second_half);
+
+        assert_intersections_meet(&first_half, &second_half, &intersections);
+        // apart from where the halves join
+        assert!(intersections.iter().any(|(t, u)| *t < 0.9 && *u > 0.1));
+    }
+}